@@ -0,0 +1,187 @@
+use macroquad::prelude::*;
+
+pub const FONT_PATH: &str = "res/OpenSans-Regular.ttf";
+// A 64x64 PNG, scaled down for the smaller sizes the window system asks for
+pub const ICON_PATH: &str = "res/icon.png";
+
+// Every asset baked into the binary, keyed by the path it would be loaded from on disk
+// WASM builds always take this path since there's no filesystem to fall back on
+#[cfg(any(feature = "embedded-assets", target_arch = "wasm32"))]
+const EMBEDDED: &[(&str, &[u8])] = &[
+    (FONT_PATH, include_bytes!("../res/OpenSans-Regular.ttf")),
+    (ICON_PATH, include_bytes!("../res/icon.png")),
+    ("res/lang/es.json", include_bytes!("../res/lang/es.json")),
+    ("res/levels/01.lvl", include_bytes!("../res/levels/01.lvl")),
+    ("res/levels/02.lvl", include_bytes!("../res/levels/02.lvl")),
+    ("res/levels/03.lvl", include_bytes!("../res/levels/03.lvl")),
+    ("res/levels/04.lvl", include_bytes!("../res/levels/04.lvl")),
+    ("res/levels/05.lvl", include_bytes!("../res/levels/05.lvl")),
+    ("res/levels/06.lvl", include_bytes!("../res/levels/06.lvl")),
+];
+
+// Returns the contents of an asset straight out of the binary
+#[cfg(any(feature = "embedded-assets", target_arch = "wasm32"))]
+pub async fn load_asset(path: &str) -> Option<Vec<u8>> {
+    read_asset_now(path)
+}
+
+// Reads an asset from disk so it can be swapped without rebuilding. Looks in the working directory,
+// then next to the executable, and in debug builds in the crate root
+#[cfg(not(any(feature = "embedded-assets", target_arch = "wasm32")))]
+pub async fn load_asset(path: &str) -> Option<Vec<u8>> {
+    let mut candidates = vec![path.to_string()];
+    if let Ok(exe) = std::env::current_exe() {
+        if let Some(dir) = exe.parent() {
+            candidates.push(dir.join(path).to_string_lossy().into_owned());
+        }
+    }
+    if cfg!(debug_assertions) {
+        candidates.push(format!("{}/{}", env!("CARGO_MANIFEST_DIR"), path));
+    }
+
+    for candidate in candidates.iter() {
+        if let Ok(bytes) = load_file(candidate).await {
+            return Some(bytes);
+        }
+    }
+    None
+}
+
+// The window icon, read before the window opens. macroquad's loaders can't run that early, so this goes
+// straight to the embedded table or the disk. None if the icon is missing, isn't 64x64, or doesn't decode,
+// and the window keeps the system's default icon
+pub fn load_window_icon() -> Option<miniquad::conf::Icon> {
+    let bytes = read_asset_now(ICON_PATH)?;
+    // The decoder panics on a corrupt file rather than returning an error
+    let image = std::panic::catch_unwind(|| Image::from_file_with_format(&bytes, Some(ImageFormat::Png))).ok()?;
+    if image.width != 64 || image.height != 64 {
+        return None;
+    }
+    let mut icon = miniquad::conf::Icon {
+        small: [0; 16 * 16 * 4],
+        medium: [0; 32 * 32 * 4],
+        big: [0; 64 * 64 * 4],
+    };
+    icon.big.copy_from_slice(&image.bytes);
+    downscale(&image.bytes, 64, &mut icon.medium, 32);
+    downscale(&image.bytes, 64, &mut icon.small, 16);
+    Some(icon)
+}
+
+#[cfg(any(feature = "embedded-assets", target_arch = "wasm32"))]
+fn read_asset_now(path: &str) -> Option<Vec<u8>> {
+    EMBEDDED
+        .iter()
+        .find(|(embedded_path, _)| *embedded_path == path)
+        .map(|(_, bytes)| bytes.to_vec())
+}
+
+// The same places load_asset looks, read with std since there's no macroquad context yet
+#[cfg(not(any(feature = "embedded-assets", target_arch = "wasm32")))]
+fn read_asset_now(path: &str) -> Option<Vec<u8>> {
+    let mut candidates = vec![std::path::PathBuf::from(path)];
+    if let Some(dir) = std::env::current_exe().ok().as_deref().and_then(std::path::Path::parent) {
+        candidates.push(dir.join(path));
+    }
+    if cfg!(debug_assertions) {
+        candidates.push(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(path));
+    }
+    candidates.iter().find_map(|candidate| std::fs::read(candidate).ok())
+}
+
+// Averages square blocks of RGBA pixels from a `from`-wide square image into a `to`-wide one
+fn downscale(pixels: &[u8], from: usize, out: &mut [u8], to: usize) {
+    let factor = from / to;
+    for y in 0..to {
+        for x in 0..to {
+            for channel in 0..4 {
+                let mut sum = 0u32;
+                for dy in 0..factor {
+                    for dx in 0..factor {
+                        sum += pixels[((y * factor + dy) * from + x * factor + dx) * 4 + channel] as u32;
+                    }
+                }
+                out[(y * to + x) * 4 + channel] = (sum / (factor * factor) as u32) as u8;
+            }
+        }
+    }
+}
+
+// Returns None if the font can't be found or parsed so the caller can fall back to the default font
+pub async fn load_font() -> Option<Font> {
+    let bytes = load_asset(FONT_PATH).await?;
+    load_ttf_font_from_bytes(&bytes).ok()
+}
+
+// Every asset loaded at startup, in load order
+#[derive(Clone, Copy)]
+enum AssetId {
+    Font,
+}
+
+const STARTUP_ASSETS: [AssetId; 1] = [AssetId::Font];
+
+impl AssetId {
+    fn path(self) -> &'static str {
+        match self {
+            AssetId::Font => FONT_PATH,
+        }
+    }
+}
+
+// Everything loaded at startup. Anything left as None falls back to a built-in default
+pub struct Assets {
+    pub font: Option<Font>,
+}
+
+impl Assets {
+    pub fn new() -> Self {
+        Self { font: None }
+    }
+}
+
+// Loads the startup assets one at a time so the loading screen can redraw in between
+pub struct AssetLoader {
+    next: usize,
+    pub failures: Vec<String>,
+}
+
+impl AssetLoader {
+    pub fn new() -> Self {
+        Self {
+            next: 0,
+            failures: Vec::new(),
+        }
+    }
+
+    pub fn loaded(&self) -> usize {
+        self.next
+    }
+
+    pub fn total(&self) -> usize {
+        STARTUP_ASSETS.len()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next >= STARTUP_ASSETS.len()
+    }
+
+    // Loads the next pending asset into assets, noting its path if it couldn't be loaded
+    pub async fn load_next(&mut self, assets: &mut Assets) {
+        let Some(&id) = STARTUP_ASSETS.get(self.next) else {
+            return;
+        };
+
+        let loaded = match id {
+            AssetId::Font => {
+                assets.font = load_font().await;
+                assets.font.is_some()
+            },
+        };
+
+        if !loaded {
+            self.failures.push(id.path().to_string());
+        }
+        self.next += 1;
+    }
+}
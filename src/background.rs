@@ -0,0 +1,175 @@
+use macroquad::prelude::*;
+
+const STAR_COUNT: usize = 300;
+// Far stars, then near ones, as (fraction of the stars, drift speed in screen heights per second, size, brightness)
+const STAR_LAYERS: [(f32, f32, f32, f32); 2] = [(0.7f32, 0.01f32, 1f32, 0.35f32), (0.3f32, 0.03f32, 2f32, 0.7f32)];
+// An explosion speeds the stars up and stretches them into streaks for this long
+const STREAK_TIME: f32 = 0.35f32;
+const STREAK_SPEEDUP: f32 = 12f32;
+// Scales streak length, which grows with star speed and the screen height
+const STREAK_LENGTH: f32 = 0.1f32;
+const GRADIENT_BANDS: usize = 24;
+const GRADIENT_TOP: Color = Color::new(0.1f32, 0.12f32, 0.22f32, 1f32);
+const GRADIENT_BOTTOM: Color = Color::new(0.3f32, 0.2f32, 0.32f32, 1f32);
+const SHAPE_COUNT: usize = 14;
+
+// Something drawn behind everything else, filling the whole screen. Positions are kept as
+// fractions of the screen so a resize never leaves gaps
+pub trait Background {
+    fn update(&mut self, dt: f32);
+    fn draw(&self);
+    // Called when an explosive block goes off. Backgrounds that don't react can ignore it
+    fn explosion(&mut self) {}
+}
+
+// Which background a board gets
+#[derive(Clone, Copy, PartialEq)]
+pub enum Theme {
+    Starfield,
+    Gradient,
+}
+
+impl Theme {
+    // Generated boards and the menus are under the stars, and campaign levels alternate
+    pub fn for_level(campaign_index: Option<usize>) -> Self {
+        match campaign_index {
+            Some(index) if index % 2 == 1 => Theme::Gradient,
+            _ => Theme::Starfield,
+        }
+    }
+
+    pub fn create(self) -> Box<dyn Background> {
+        match self {
+            Theme::Starfield => Box::new(Starfield::new()),
+            Theme::Gradient => Box::new(FloatingShapes::new()),
+        }
+    }
+}
+
+struct Star {
+    pos: Vec2,
+    layer: usize,
+}
+
+// Points drifting slowly down the screen at two depths, the nearer ones faster and brighter
+pub struct Starfield {
+    stars: Vec<Star>,
+    streak: f32,
+}
+
+impl Starfield {
+    pub fn new() -> Self {
+        let far = (STAR_COUNT as f32 * STAR_LAYERS[0].0) as usize;
+        let stars = (0..STAR_COUNT)
+            .map(|i| Star {
+                pos: vec2(rand::gen_range(0f32, 1f32), rand::gen_range(0f32, 1f32)),
+                layer: if i < far { 0 } else { 1 },
+            })
+            .collect();
+        Self { stars, streak: 0f32 }
+    }
+
+    fn speedup(&self) -> f32 {
+        1f32 + (STREAK_SPEEDUP - 1f32) * self.streak / STREAK_TIME
+    }
+}
+
+impl Background for Starfield {
+    fn update(&mut self, dt: f32) {
+        let speedup = self.speedup();
+        for star in self.stars.iter_mut() {
+            star.pos.y += STAR_LAYERS[star.layer].1 * speedup * dt;
+            if star.pos.y > 1f32 {
+                star.pos.y -= 1f32;
+                star.pos.x = rand::gen_range(0f32, 1f32);
+            }
+        }
+        self.streak = (self.streak - dt).max(0f32);
+    }
+
+    fn draw(&self) {
+        let screen = vec2(screen_width(), screen_height());
+        // Tails shrink back to points as the streak wears off, longer for the faster near stars
+        let stretch = self.speedup() - 1f32;
+        for star in self.stars.iter() {
+            let (_, speed, size, brightness) = STAR_LAYERS[star.layer];
+            let color = Color::new(1f32, 1f32, 1f32, brightness);
+            let pos = star.pos * screen;
+            if stretch > 0f32 {
+                let length = speed * stretch * STREAK_LENGTH * screen.y;
+                draw_line(pos.x, pos.y - length, pos.x, pos.y, size, color);
+            } else {
+                draw_rectangle(pos.x, pos.y, size, size, color);
+            }
+        }
+    }
+
+    fn explosion(&mut self) {
+        self.streak = STREAK_TIME;
+    }
+}
+
+struct Shape {
+    pos: Vec2,
+    // Screen heights per second, upwards
+    speed: f32,
+    size: f32,
+    round: bool,
+    // Offsets the sideways sway so the shapes don't move in step
+    phase: f32,
+}
+
+// A soft vertical gradient with faint shapes rising through it
+pub struct FloatingShapes {
+    shapes: Vec<Shape>,
+    time: f32,
+}
+
+impl FloatingShapes {
+    pub fn new() -> Self {
+        let shapes = (0..SHAPE_COUNT)
+            .map(|_| Shape {
+                pos: vec2(rand::gen_range(0f32, 1f32), rand::gen_range(0f32, 1.2f32)),
+                speed: rand::gen_range(0.01f32, 0.03f32),
+                size: rand::gen_range(20f32, 70f32),
+                round: rand::gen_range(0, 2) == 0,
+                phase: rand::gen_range(0f32, std::f32::consts::TAU),
+            })
+            .collect();
+        Self { shapes, time: 0f32 }
+    }
+}
+
+impl Background for FloatingShapes {
+    fn update(&mut self, dt: f32) {
+        self.time += dt;
+        for shape in self.shapes.iter_mut() {
+            shape.pos.y -= shape.speed * dt;
+            // Starts again below the bottom edge once it's fully off the top
+            if shape.pos.y < -0.2f32 {
+                shape.pos.y += 1.4f32;
+                shape.pos.x = rand::gen_range(0f32, 1f32);
+            }
+        }
+    }
+
+    fn draw(&self) {
+        let band_height = screen_height() / GRADIENT_BANDS as f32;
+        for i in 0..GRADIENT_BANDS {
+            let t = i as f32 / (GRADIENT_BANDS - 1) as f32;
+            let color = Color::from_vec(GRADIENT_TOP.to_vec().lerp(GRADIENT_BOTTOM.to_vec(), t));
+            draw_rectangle(0f32, i as f32 * band_height, screen_width(), band_height + 1f32, color);
+        }
+
+        let color = Color::new(1f32, 1f32, 1f32, 0.06f32);
+        for shape in self.shapes.iter() {
+            let sway = (self.time * 0.5f32 + shape.phase).sin() * 20f32;
+            let (x, y) = (shape.pos.x * screen_width() + sway, shape.pos.y * screen_height());
+            if shape.round {
+                draw_circle(x, y, shape.size * 0.5f32, color);
+            } else {
+                draw_rectangle(x - shape.size * 0.5f32, y - shape.size * 0.5f32, shape.size, shape.size, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use macroquad::prelude::*;
+
+use crate::effects::EffectsPolicy;
+
+// How quickly shake and zoom punch die away, as a fraction lost per second
+const SHAKE_DECAY: f32 = 8f32;
+const PUNCH_DECAY: f32 = 10f32;
+
+// Camera effects for the playfield. Screen shake and zoom punches are folded into one Camera2D
+// so every world draw picks them up, while HUD text drawn after set_default_camera stays still
+pub struct CameraFx {
+    // Maximum shake offset in pixels, decaying to zero
+    shake: f32,
+    // Extra zoom as a fraction, so 0.02 is a 2% punch in
+    punch: f32,
+    offset: Vec2,
+}
+
+impl CameraFx {
+    pub fn new() -> Self {
+        Self {
+            shake: 0f32,
+            punch: 0f32,
+            offset: Vec2::ZERO,
+        }
+    }
+
+    // Overlapping effects take the strongest rather than stacking
+    pub fn shake(&mut self, amount: f32) {
+        self.shake = self.shake.max(amount);
+    }
+
+    pub fn punch(&mut self, amount: f32) {
+        self.punch = self.punch.max(amount);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.shake = (self.shake - self.shake * SHAKE_DECAY * dt).max(0f32);
+        self.punch = (self.punch - self.punch * PUNCH_DECAY * dt).max(0f32);
+
+        self.offset = if self.shake > 0.5f32 {
+            vec2(rand::gen_range(-1f32, 1f32), rand::gen_range(-1f32, 1f32)) * self.shake
+        } else {
+            Vec2::ZERO
+        };
+    }
+
+    // Sets up the world camera for this frame. World coordinates match screen pixels when no effect is
+    // active, or whenever the policy turns screen shake off
+    pub fn apply(&self, effects: &EffectsPolicy) {
+        let mut camera = Camera2D::from_display_rect(Rect::new(0f32, 0f32, screen_width(), screen_height()));
+        if effects.screen_shake {
+            camera.target += self.offset;
+            camera.zoom *= 1f32 + self.punch;
+        }
+        set_camera(&camera);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::level::Level;
+use crate::BlockType;
+
+// Where the editor imports and exports classic layouts
+pub const CLASSIC_LEVEL_PATH: &str = "level.txt";
+
+// Why a classic layout couldn't be read, and where. Lines and columns count from 1
+#[derive(Clone, Copy)]
+pub struct LayoutError {
+    pub kind: LayoutErrorKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, Copy)]
+pub enum LayoutErrorKind {
+    // A character that isn't one of the block characters
+    UnknownCharacter,
+    // A row longer or shorter than the first one
+    RaggedRow,
+    // Nothing but blank lines
+    Empty,
+}
+
+impl LayoutError {
+    // Shown with {line} and {column} filled in
+    pub fn text_id(&self) -> &'static str {
+        match self.kind {
+            LayoutErrorKind::UnknownCharacter => "editor.layout_unknown_character",
+            LayoutErrorKind::RaggedRow => "editor.layout_ragged_row",
+            LayoutErrorKind::Empty => "editor.layout_empty",
+        }
+    }
+}
+
+// Whether a level file is a classic layout rather than the game's own format, going by its extension
+pub fn is_classic_path(path: &str) -> bool {
+    path.ends_with(".txt")
+}
+
+// The ASCII art layouts older Breakout clones used, one line per row and one character per cell:
+// `.` empty, `1` or `2` a regular block with that many lives, `S` spawn-ball, `X` indestructible and
+// `E` explosive. Unlike the game's own format there are no settings, comments or portals, and every
+// row has to be the same width. Blank lines after the last row are ignored
+pub fn parse_classic(text: &str) -> Result<Level, LayoutError> {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let rows = &lines[..lines.iter().rposition(|line| !line.is_empty()).map_or(0, |last| last + 1)];
+    let columns = rows.first().map_or(0, |row| row.chars().count());
+    if columns == 0 {
+        return Err(LayoutError {
+            kind: LayoutErrorKind::Empty,
+            line: 1,
+            column: 1,
+        });
+    }
+
+    let mut level = Level::empty(columns, rows.len());
+    for (row, line) in rows.iter().enumerate() {
+        let width = line.chars().count();
+        if width != columns {
+            return Err(LayoutError {
+                kind: LayoutErrorKind::RaggedRow,
+                line: row + 1,
+                column: width.min(columns) + 1,
+            });
+        }
+        for (column, character) in line.chars().enumerate() {
+            let cell = match character {
+                '.' => None,
+                '1' | '2' => Some(BlockType::Regular),
+                'S' => Some(BlockType::SpawnBallOnDeath),
+                'X' => Some(BlockType::Indestructible),
+                'E' => Some(BlockType::Explosive),
+                _ => {
+                    return Err(LayoutError {
+                        kind: LayoutErrorKind::UnknownCharacter,
+                        line: row + 1,
+                        column: column + 1,
+                    })
+                },
+            };
+            level.set_cell(column, row, cell);
+            level.set_worn(column, row, character == '1');
+        }
+    }
+    Ok(level)
+}
+
+// Writes a level as a classic layout. Fails with the first block type the format has no character for,
+// since traps, portals and brittle blocks would otherwise be lost without a word. Level settings are dropped
+pub fn to_classic(level: &Level) -> Result<String, BlockType> {
+    let mut text = String::new();
+    for row in 0..level.rows() {
+        for column in 0..level.columns {
+            let character = match level.cell(column, row) {
+                None => '.',
+                Some(BlockType::Regular) if level.is_worn(column, row) => '1',
+                Some(BlockType::Regular) => '2',
+                Some(BlockType::SpawnBallOnDeath) => 'S',
+                Some(BlockType::Indestructible) => 'X',
+                Some(BlockType::Explosive) => 'E',
+                Some(block_type) => return Err(block_type),
+            };
+            text.push(character);
+        }
+        text.push('\n');
+    }
+    Ok(text)
+}
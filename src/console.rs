@@ -0,0 +1,162 @@
+use macroquad::prelude::*;
+
+use crate::level::Level;
+use crate::settings::Settings;
+use crate::Game;
+
+const MAX_OUTPUT_LINES: usize = 8;
+const CONSOLE_FONT_SIZE: u16 = 20;
+const CONSOLE_LINE_HEIGHT: f32 = 24f32;
+
+// A minimal developer console toggled with the backtick key
+// Commands go through the same Game methods the rest of the game uses, and any cheat flags the run
+pub struct Console {
+    pub open: bool,
+    pub show_fps: bool,
+    input: String,
+    output: Vec<String>,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            show_fps: false,
+            input: String::new(),
+            output: Vec::new(),
+        }
+    }
+
+    // Returns true while the console is open, meaning it owns the keyboard this frame
+    pub fn update(&mut self, game: &mut Game, settings: &Settings) -> bool {
+        // macroquad hands typed characters out last-in first-out and never clears them,
+        // so drain the whole queue every frame and put it back in typing order
+        let mut typed = Vec::new();
+        while let Some(character) = get_char_pressed() {
+            typed.push(character);
+        }
+        typed.reverse();
+
+        if is_key_pressed(KeyCode::GraveAccent) {
+            self.open = !self.open;
+            return true;
+        }
+
+        if !self.open {
+            return false;
+        }
+
+        for character in typed {
+            if character != '`' && !character.is_control() {
+                self.input.push(character);
+            }
+        }
+
+        if is_key_pressed(KeyCode::Backspace) {
+            self.input.pop();
+        }
+
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            let line = std::mem::take(&mut self.input);
+            if !line.trim().is_empty() {
+                self.print(format!("> {}", line));
+                match self.run(&line, game, settings) {
+                    Ok(reply) => self.print(reply),
+                    Err(error) => self.print(format!("error: {}", error)),
+                }
+            }
+        }
+
+        true
+    }
+
+    fn print(&mut self, line: String) {
+        self.output.push(line);
+        if self.output.len() > MAX_OUTPUT_LINES {
+            self.output.remove(0);
+        }
+    }
+
+    fn run(&mut self, line: &str, game: &mut Game, settings: &Settings) -> Result<String, String> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let arg = words.next();
+
+        let reply = match command {
+            "fps" => {
+                // Not a cheat, so it doesn't flag the run
+                self.show_fps = !self.show_fps;
+                return Ok(format!("fps display {}", if self.show_fps { "on" } else { "off" }));
+            },
+            // Not a cheat either, since it only reads the board
+            "export" => {
+                let (level, snapped) = Level::from_blocks(&game.blocks, game.board_padding());
+                let path = level.export().map_err(|error| format!("couldn't export: {}", error))?;
+                return Ok(match snapped {
+                    0 => format!("saved {}", path),
+                    _ => format!("saved {} ({} blocks snapped to the grid)", path, snapped),
+                });
+            },
+            "skiplevel" => {
+                game.skip_level();
+                "level skipped".to_string()
+            },
+            "addlife" => {
+                let count = parse_arg::<i32>(arg, "addlife N")?;
+                game.add_lives(count);
+                format!("lives: {}", game.player_lives)
+            },
+            "spawnball" => {
+                let count = parse_arg::<usize>(arg, "spawnball N")?;
+                game.spawn_balls(count);
+                format!("balls: {}", game.balls.len())
+            },
+            "setseed" => {
+                let seed = parse_arg::<u64>(arg, "setseed X")?;
+                game.set_seed(seed, settings);
+                format!("seed: {}", seed)
+            },
+            "give" => {
+                let name = arg.ok_or("usage: give POWERUP")?;
+                game.give_power_up(name)?;
+                format!("gave {}", name)
+            },
+            _ => return Err(format!("unknown command '{}'", command)),
+        };
+
+        game.cheated = true;
+        Ok(reply)
+    }
+
+    pub fn draw(&self, font: Font) {
+        if !self.open {
+            return;
+        }
+
+        let height = CONSOLE_LINE_HEIGHT * (MAX_OUTPUT_LINES + 1) as f32 + 12f32;
+        draw_rectangle(0f32, 0f32, screen_width(), height, Color::new(0f32, 0f32, 0f32, 0.75f32));
+
+        let params = TextParams {
+            font,
+            font_size: CONSOLE_FONT_SIZE,
+            color: LIGHTGRAY,
+            ..Default::default()
+        };
+
+        for (i, line) in self.output.iter().enumerate() {
+            draw_text_ex(line, 10f32, CONSOLE_LINE_HEIGHT * (i + 1) as f32, params);
+        }
+
+        draw_text_ex(
+            &format!("] {}_", self.input),
+            10f32,
+            CONSOLE_LINE_HEIGHT * (MAX_OUTPUT_LINES + 1) as f32,
+            TextParams { color: WHITE, ..params },
+        );
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(arg: Option<&str>, usage: &str) -> Result<T, String> {
+    arg.and_then(|arg| arg.parse().ok())
+        .ok_or_else(|| format!("usage: {}", usage))
+}
@@ -0,0 +1,94 @@
+use macroquad::prelude::*;
+
+use crate::lang::Lang;
+use crate::menu::Menu;
+use crate::skins::{Skin, MAX_TRAIL_LENGTH, SKINS};
+use crate::stats::Stats;
+use crate::{draw_centered_text, BALL_SIZE, PADDLE_SIZE};
+
+// Where the live preview of the highlighted skin sits, and how far its ball swings
+const PREVIEW_TOP: f32 = 450f32;
+const PREVIEW_SIZE: Vec2 = const_vec2!([400f32, 150f32]);
+
+// What the main loop should do after a customize frame
+pub enum CustomizeAction {
+    Equip(&'static Skin),
+    Back,
+}
+
+// A list of every skin with a preview of the highlighted one. Locked skins can be looked at
+// but not equipped, and show what unlocks them instead
+pub struct Customize {
+    menu: Menu<usize>,
+}
+
+impl Customize {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new((0..SKINS.len()).collect()),
+        }
+    }
+
+    pub fn update(&mut self, stats: &Stats, confirm: bool) -> Option<CustomizeAction> {
+        if let Some(index) = self.menu.handle_input(confirm || is_key_pressed(KeyCode::Enter)) {
+            let skin = &SKINS[index];
+            if skin.is_unlocked(stats) {
+                return Some(CustomizeAction::Equip(skin));
+            }
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            return Some(CustomizeAction::Back);
+        }
+        None
+    }
+
+    pub fn draw(&self, equipped: &str, stats: &Stats, lang: &Lang, font: Font) {
+        draw_centered_text(lang.tr("customize.title"), 100f32, font);
+        let labels: Vec<String> = SKINS.iter().map(|skin| lang.tr(skin.text_id).to_string()).collect();
+        self.menu.draw(&labels, 170f32, 45f32, font);
+
+        let skin = &SKINS[self.menu.selected];
+        draw_preview(skin);
+
+        let status = if !skin.is_unlocked(stats) {
+            // Grays the preview out under the unlock condition
+            draw_rectangle(
+                (screen_width() - PREVIEW_SIZE.x) * 0.5f32,
+                PREVIEW_TOP,
+                PREVIEW_SIZE.x,
+                PREVIEW_SIZE.y,
+                Color::new(0.31f32, 0.31f32, 0.31f32, 0.8f32),
+            );
+            skin.unlock_text(lang)
+        } else if skin.name == equipped {
+            lang.tr("customize.equipped").to_string()
+        } else {
+            lang.tr("customize.equip").to_string()
+        };
+        draw_centered_text(&status, PREVIEW_TOP + PREVIEW_SIZE.y + 30f32, font);
+        draw_centered_text(lang.tr("customize.help"), screen_height() - 30f32, font);
+    }
+}
+
+// A paddle with a ball looping above it, drawn with the skin so trails can be seen in motion
+fn draw_preview(skin: &Skin) {
+    let center_x = screen_width() * 0.5f32;
+    let paddle = Rect::new(
+        center_x - PADDLE_SIZE.x * 0.5f32,
+        PREVIEW_TOP + PREVIEW_SIZE.y - PADDLE_SIZE.y - 10f32,
+        PADDLE_SIZE.x,
+        PADDLE_SIZE.y,
+    );
+    skin.draw_paddle(paddle, 0f32, skin.paddle);
+
+    // A figure eight, sampled back in time for the trail
+    let ball_center = |t: f64| {
+        let t = t as f32 * 2f32;
+        vec2(center_x + t.sin() * 150f32, PREVIEW_TOP + 45f32 + (t * 2f32).sin() * 20f32)
+    };
+    let now = get_time();
+    let trail: Vec<Vec2> = (0..MAX_TRAIL_LENGTH).rev().map(|i| ball_center(now - i as f64 / 60f64)).collect();
+    skin.draw_trail(&trail, BALL_SIZE);
+    let center = ball_center(now);
+    skin.draw_ball(Rect::new(center.x - BALL_SIZE * 0.5f32, center.y - BALL_SIZE * 0.5f32, BALL_SIZE, BALL_SIZE), skin.ball);
+}
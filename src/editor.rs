@@ -0,0 +1,254 @@
+use macroquad::prelude::*;
+
+use crate::classic_level::{is_classic_path, parse_classic, to_classic, CLASSIC_LEVEL_PATH};
+use crate::lang::Lang;
+use crate::input::clipboard_set;
+use crate::level::{encode_level, Level, EDITOR_LEVEL_PATH, EXPORTS_DIR};
+use crate::{draw_centered_text, Block, BlockType, BoardLayout};
+
+// Size of the editing grid. Saved levels always use the full grid so the layout doesn't shift between edits
+const EDITOR_COLUMNS: usize = 10;
+const EDITOR_ROWS: usize = 8;
+// Block types the scroll wheel cycles through, in order
+const PALETTE: [BlockType; 8] = [
+    BlockType::Regular,
+    BlockType::SpawnBallOnDeath,
+    BlockType::Explosive,
+    BlockType::Indestructible,
+    BlockType::Trap,
+    BlockType::Brittle,
+    BlockType::Portal(0),
+    BlockType::Portal(1),
+];
+const BUTTON_SIZE: Vec2 = const_vec2!([160f32, 40f32]);
+const SWATCH_SIZE: Vec2 = const_vec2!([50f32, 20f32]);
+// How long the saved, copied and import messages stay up
+const STATUS_TIME: f32 = 2f32;
+
+// What the main loop should do after an editor frame
+pub enum EditorAction {
+    TestPlay,
+    Back,
+}
+
+// Places blocks on a grid with the mouse: left click paints the selected type, right click clears,
+// and the scroll wheel picks the type
+pub struct Editor {
+    pub level: Level,
+    selected: usize,
+    status: &'static str,
+    // The file the status is about, and the line and column it points at for a layout that didn't parse
+    status_path: String,
+    status_at: Option<(usize, usize)>,
+    status_timer: f32,
+}
+
+impl Editor {
+    // Picks up where the last saved layout left off. A layout that can't be read starts the grid empty
+    // and says why
+    pub fn new() -> Self {
+        let mut editor = Self {
+            level: Level::empty(EDITOR_COLUMNS, EDITOR_ROWS),
+            selected: 0,
+            status: "",
+            status_path: EDITOR_LEVEL_PATH.to_string(),
+            status_at: None,
+            status_timer: 0f32,
+        };
+        if std::path::Path::new(EDITOR_LEVEL_PATH).exists() {
+            editor.open(EDITOR_LEVEL_PATH);
+        }
+        editor
+    }
+
+    pub fn update(&mut self) -> Option<EditorAction> {
+        self.status_timer = (self.status_timer - get_frame_time()).max(0f32);
+
+        let (_, wheel) = mouse_wheel();
+        if wheel > 0f32 {
+            self.selected = (self.selected + 1) % PALETTE.len();
+        } else if wheel < 0f32 {
+            self.selected = (self.selected + PALETTE.len() - 1) % PALETTE.len();
+        }
+
+        let mouse = Vec2::from(mouse_position());
+        if let Some(cell) = self.cell_at(mouse) {
+            if is_mouse_button_down(MouseButton::Left) {
+                self.level.cells[cell] = Some(PALETTE[self.selected]);
+                self.level.worn[cell] = false;
+            } else if is_mouse_button_down(MouseButton::Right) {
+                self.level.cells[cell] = None;
+                self.level.worn[cell] = false;
+            }
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let (save, test_play) = buttons();
+            if save.contains(mouse) {
+                self.save(EDITOR_LEVEL_PATH);
+            } else if test_play.contains(mouse) {
+                // Sent even while the button's grayed out, so the caller can say why it can't start
+                return Some(EditorAction::TestPlay);
+            }
+        }
+
+        if is_key_pressed(KeyCode::C) {
+            clipboard_set(&encode_level(&self.level.blocks(), self.level.padding));
+            self.show_status("editor.code_copied", EDITOR_LEVEL_PATH, None);
+        }
+
+        if is_key_pressed(KeyCode::I) {
+            self.open(CLASSIC_LEVEL_PATH);
+        }
+        if is_key_pressed(KeyCode::X) {
+            self.save(CLASSIC_LEVEL_PATH);
+        }
+        if is_key_pressed(KeyCode::L) {
+            match self.level.export() {
+                Ok(path) => self.show_status("editor.exported", &path, None),
+                Err(_) => self.show_status("editor.export_failed", EXPORTS_DIR, None),
+            }
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            return Some(EditorAction::Back);
+        }
+        None
+    }
+
+    // Writes the grid as a classic layout or in the game's own format, going by the file's extension
+    fn save(&mut self, path: &'static str) {
+        let text = if is_classic_path(path) {
+            match to_classic(&self.level) {
+                Ok(text) => text,
+                Err(_) => return self.show_status("editor.layout_unsupported", path, None),
+            }
+        } else {
+            self.level.to_text()
+        };
+        match std::fs::write(path, text) {
+            Ok(()) => self.show_status("editor.saved", path, None),
+            Err(_) => self.show_status("editor.save_failed", path, None),
+        }
+    }
+
+    // Replaces the grid with a saved layout, read the same way it would have been written. Layouts
+    // bigger than the grid are cut off at its edges
+    fn open(&mut self, path: &'static str) {
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return self.show_status("editor.open_failed", path, None);
+        };
+        let saved = if is_classic_path(path) {
+            parse_classic(&text).map_err(|error| (error.text_id(), Some((error.line, error.column))))
+        } else {
+            Level::parse(&text).map_err(|error| (error.text_id(), None))
+        };
+        let saved = match saved {
+            Ok(saved) => saved,
+            Err((text_id, at)) => return self.show_status(text_id, path, at),
+        };
+
+        self.level = Level::empty(EDITOR_COLUMNS, EDITOR_ROWS);
+        for row in 0..saved.rows().min(EDITOR_ROWS) {
+            for column in 0..saved.columns.min(EDITOR_COLUMNS) {
+                self.level.set_cell(column, row, saved.cell(column, row));
+                self.level.set_worn(column, row, saved.is_worn(column, row));
+            }
+        }
+        if is_classic_path(path) {
+            self.show_status("editor.imported", path, None);
+        }
+    }
+
+    pub fn show_status(&mut self, text_id: &'static str, path: &str, at: Option<(usize, usize)>) {
+        self.status = text_id;
+        self.status_path = path.to_string();
+        self.status_at = at;
+        self.status_timer = STATUS_TIME;
+    }
+
+    fn playable(&self) -> bool {
+        self.level.playable_blocks().is_ok()
+    }
+
+    // Index of the grid cell under a point, snapped the same way blocks are laid out
+    fn cell_at(&self, point: Vec2) -> Option<usize> {
+        let layout = BoardLayout::fit(EDITOR_COLUMNS);
+        let cell = (point - layout.origin) / layout.cell_size;
+        if cell.x < 0f32 || cell.y < 0f32 || cell.x >= EDITOR_COLUMNS as f32 || cell.y >= EDITOR_ROWS as f32 {
+            return None;
+        }
+        Some(cell.y as usize * EDITOR_COLUMNS + cell.x as usize)
+    }
+
+    pub fn draw(&self, lang: &Lang, font: Font) {
+        let layout = BoardLayout::fit(EDITOR_COLUMNS);
+        let grid_color = Color::new(1f32, 1f32, 1f32, 0.15f32);
+        for i in 0..EDITOR_COLUMNS * EDITOR_ROWS {
+            let pos = layout.origin + vec2((i % EDITOR_COLUMNS) as f32, (i / EDITOR_COLUMNS) as f32) * layout.cell_size;
+            draw_rectangle_lines(pos.x, pos.y, layout.block_size.x, layout.block_size.y, 1f32, grid_color);
+        }
+        for block in self.level.blocks() {
+            block.draw();
+        }
+
+        if let Some(cell) = self.cell_at(Vec2::from(mouse_position())) {
+            let pos = layout.origin + vec2((cell % EDITOR_COLUMNS) as f32, (cell / EDITOR_COLUMNS) as f32) * layout.cell_size;
+            draw_rectangle_lines(pos.x, pos.y, layout.block_size.x, layout.block_size.y, 3f32, WHITE);
+        }
+
+        // The palette along the bottom left, with the selected type outlined
+        let palette_y = screen_height() - 60f32;
+        for (i, block_type) in PALETTE.iter().enumerate() {
+            let pos = vec2(20f32 + i as f32 * (SWATCH_SIZE.x + 10f32), palette_y);
+            Block::new(pos, SWATCH_SIZE, *block_type).draw();
+            if i == self.selected {
+                draw_rectangle_lines(pos.x - 4f32, pos.y - 4f32, SWATCH_SIZE.x + 8f32, SWATCH_SIZE.y + 8f32, 2f32, WHITE);
+            }
+        }
+
+        let (save, test_play) = buttons();
+        draw_button(save, lang.tr("editor.save"), true, font);
+        draw_button(test_play, lang.tr("editor.test_play"), self.playable(), font);
+
+        draw_centered_text(lang.tr("editor.help"), screen_height() - 90f32, font);
+        if self.status_timer > 0f32 {
+            let mut status = lang.tr(self.status).replace("{path}", &self.status_path);
+            if let Some((line, column)) = self.status_at {
+                status = status.replace("{line}", &line.to_string()).replace("{column}", &column.to_string());
+            }
+            draw_centered_text(&status, screen_height() - 130f32, font);
+        }
+    }
+}
+
+// Save and test play, in the bottom right corner
+fn buttons() -> (Rect, Rect) {
+    let y = screen_height() - 70f32;
+    let test_play_x = screen_width() - 20f32 - BUTTON_SIZE.x;
+    let save_x = test_play_x - 20f32 - BUTTON_SIZE.x;
+    (
+        Rect::new(save_x, y, BUTTON_SIZE.x, BUTTON_SIZE.y),
+        Rect::new(test_play_x, y, BUTTON_SIZE.x, BUTTON_SIZE.y),
+    )
+}
+
+fn draw_button(rect: Rect, text: &str, enabled: bool, font: Font) {
+    let color = if enabled { WHITE } else { GRAY };
+    let hovered = enabled && rect.contains(Vec2::from(mouse_position()));
+    draw_rectangle(rect.x, rect.y, rect.w, rect.h, if hovered { Color::new(1f32, 1f32, 1f32, 0.2f32) } else { BLACK });
+    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2f32, color);
+
+    let dims = measure_text(text, Some(font), 24u16, 1f32);
+    draw_text_ex(
+        text,
+        rect.x + (rect.w - dims.width) * 0.5f32,
+        rect.y + (rect.h + dims.height) * 0.5f32,
+        TextParams {
+            font,
+            font_size: 24u16,
+            color,
+            ..Default::default()
+        },
+    );
+}
@@ -0,0 +1,40 @@
+use crate::settings::Settings;
+
+// Most particles alive at once when effects are reduced
+const REDUCED_MAX_PARTICLES: usize = 40;
+
+// Which kinds of visual juice are allowed. The effects code asks this instead of reading settings
+// itself, and it's rebuilt from the settings every frame so a change applies straight away
+#[derive(Clone, Copy)]
+pub struct EffectsPolicy {
+    // Camera shake and zoom punches
+    pub screen_shake: bool,
+    // Solid flashes. When off, flashes become a pulse of an outline instead
+    pub flashes: bool,
+    pub max_particles: usize,
+    pub animate_background: bool,
+}
+
+impl EffectsPolicy {
+    pub const FULL: EffectsPolicy = EffectsPolicy {
+        screen_shake: true,
+        flashes: true,
+        max_particles: usize::MAX,
+        animate_background: true,
+    };
+
+    pub const REDUCED: EffectsPolicy = EffectsPolicy {
+        screen_shake: false,
+        flashes: false,
+        max_particles: REDUCED_MAX_PARTICLES,
+        animate_background: false,
+    };
+
+    pub fn from_settings(settings: &Settings) -> Self {
+        if settings.reduced_motion {
+            Self::REDUCED
+        } else {
+            Self::FULL
+        }
+    }
+}
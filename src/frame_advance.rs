@@ -0,0 +1,67 @@
+use macroquad::prelude::*;
+
+use crate::Game;
+
+// Game time each F11 press runs the simulation forward by
+const FRAME_ADVANCE_STEP: f32 = 1f32 / 60f32;
+const OVERLAY_FONT_SIZE: f32 = 20f32;
+const OVERLAY_LINE_HEIGHT: f32 = 20f32;
+
+// Debug stepping, only with --debug. F10 freezes play and F11 then runs exactly one physics tick per
+// press. Everything else keeps updating and drawing while frozen, and held keys are read as usual, so
+// whatever is held down lands on the advanced tick
+pub struct FrameAdvance {
+    enabled: bool,
+    pub frozen: bool,
+    // Physics ticks run so far, counting only ones where time moved
+    pub tick: u64,
+    // What the latest tick queued, by name
+    pub last_events: Vec<&'static str>,
+}
+
+impl FrameAdvance {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            frozen: false,
+            tick: 0,
+            last_events: Vec::new(),
+        }
+    }
+
+    // The time to simulate this frame: the real frame time normally, and while frozen either nothing
+    // or one step if F11 was pressed
+    pub fn frame_time(&mut self, real: f32) -> f32 {
+        if !self.enabled {
+            return real;
+        }
+        if is_key_pressed(KeyCode::F10) {
+            self.frozen = !self.frozen;
+        }
+        match (self.frozen, is_key_pressed(KeyCode::F11)) {
+            (false, _) => real,
+            (true, true) => FRAME_ADVANCE_STEP,
+            (true, false) => 0f32,
+        }
+    }
+
+    // The tick, each ball's velocity in pixels per second, and the latest events, down the left side
+    pub fn draw(&self, game: &Game) {
+        if !self.frozen {
+            return;
+        }
+        let mut lines = vec![format!("frozen at tick {}  (F10 resume, F11 step)", self.tick)];
+        for (i, ball) in game.balls.iter().enumerate() {
+            let vel = ball.vel * crate::BALL_SPEED * ball.speed_scale;
+            lines.push(format!("ball {}: pos ({:.1}, {:.1})  vel ({:.1}, {:.1})", i, ball.rect.x, ball.rect.y, vel.x, vel.y));
+        }
+        let events = if self.last_events.is_empty() { "none".to_string() } else { self.last_events.join(", ") };
+        lines.push(format!("events: {}", events));
+
+        let top = screen_height() * 0.5f32 - lines.len() as f32 * OVERLAY_LINE_HEIGHT * 0.5f32;
+        draw_rectangle(0f32, top - OVERLAY_LINE_HEIGHT, 520f32, (lines.len() + 1) as f32 * OVERLAY_LINE_HEIGHT, Color::new(0f32, 0f32, 0f32, 0.6f32));
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, 10f32, top + i as f32 * OVERLAY_LINE_HEIGHT, OVERLAY_FONT_SIZE, WHITE);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use macroquad::miniquad;
+use macroquad::prelude::*;
+
+use crate::session_log::SESSIONS_DIR;
+
+// The screen is split into this many cells, 16:9 so they come out close to square on most windows
+pub const HEATMAP_COLUMNS: usize = 64;
+pub const HEATMAP_ROWS: usize = 36;
+// Strongest tint of the overlay, over the busiest cell
+const OVERLAY_ALPHA: f32 = 0.6f32;
+
+// How often a ball was in each part of the screen over a run, counted once per ball per physics step.
+// Positions are taken as fractions of the screen so a resize mid-run keeps counting into the same cells
+pub struct Heatmap {
+    counts: [[u32; HEATMAP_COLUMNS]; HEATMAP_ROWS],
+    // Milliseconds timestamp the run's files are named after, picked when it's first saved so a run
+    // that ends in more than one game over keeps rewriting the same files
+    file_stamp: Option<u64>,
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        Self {
+            counts: [[0; HEATMAP_COLUMNS]; HEATMAP_ROWS],
+            file_stamp: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.counts = [[0; HEATMAP_COLUMNS]; HEATMAP_ROWS];
+        self.file_stamp = None;
+    }
+
+    // Anything off the screen is counted in the nearest edge cell
+    pub fn record(&mut self, pos: Vec2) {
+        let column = (pos.x / screen_width() * HEATMAP_COLUMNS as f32).clamp(0f32, (HEATMAP_COLUMNS - 1) as f32) as usize;
+        let row = (pos.y / screen_height() * HEATMAP_ROWS as f32).clamp(0f32, (HEATMAP_ROWS - 1) as f32) as usize;
+        self.counts[row][column] = self.counts[row][column].saturating_add(1);
+    }
+
+    fn max(&self) -> u32 {
+        self.counts.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    // Writes sessions/<timestamp>-heatmap.pgm, scaled so the busiest cell is white, and the raw counts
+    // beside it as one line of numbers per row. Like the session log this is best effort
+    pub fn save(&mut self) {
+        if std::fs::create_dir_all(SESSIONS_DIR).is_err() {
+            return;
+        }
+        let stamp = *self.file_stamp.get_or_insert((miniquad::date::now() * 1000f64) as u64);
+        let path = format!("{}/{}-heatmap", SESSIONS_DIR, stamp);
+        let _ = self.write_pgm(&format!("{}.pgm", path));
+        let _ = self.write_counts(&format!("{}.txt", path));
+    }
+
+    fn write_pgm(&self, path: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "P2\n{} {}\n255", HEATMAP_COLUMNS, HEATMAP_ROWS)?;
+        let max = self.max().max(1) as f32;
+        for row in self.counts.iter() {
+            let line: Vec<String> = row.iter().map(|&count| ((count as f32 / max) * 255f32).round().to_string()).collect();
+            writeln!(writer, "{}", line.join(" "))?;
+        }
+        writer.flush()
+    }
+
+    fn write_counts(&self, path: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for row in self.counts.iter() {
+            let line: Vec<String> = row.iter().map(|count| count.to_string()).collect();
+            writeln!(writer, "{}", line.join(" "))?;
+        }
+        writer.flush()
+    }
+
+    // Tints each visited cell red, stronger the more it was visited. Cells the ball never reached are left clear
+    pub fn draw_overlay(&self) {
+        let max = self.max();
+        if max == 0 {
+            return;
+        }
+        let cell = vec2(screen_width() / HEATMAP_COLUMNS as f32, screen_height() / HEATMAP_ROWS as f32);
+        for (row, counts) in self.counts.iter().enumerate() {
+            for (column, &count) in counts.iter().enumerate() {
+                if count > 0 {
+                    let alpha = OVERLAY_ALPHA * (count as f32 / max as f32).sqrt();
+                    draw_rectangle(column as f32 * cell.x, row as f32 * cell.y, cell.x, cell.y, Color::new(1f32, 0.2f32, 0.1f32, alpha));
+                }
+            }
+        }
+    }
+}
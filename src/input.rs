@@ -0,0 +1,93 @@
+use macroquad::prelude::*;
+
+// How many frames a press stays available after it happens
+const BUFFER_FRAMES: u32 = 6;
+
+// Remembers a key press for a few frames so a press that lands just before a state is ready,
+// like tapping Space as the serve becomes available, still counts once it is
+pub struct InputBuffer {
+    key: KeyCode,
+    frames: u32,
+    frames_left: u32,
+}
+
+impl InputBuffer {
+    pub fn new(key: KeyCode) -> Self {
+        Self::with_frames(key, BUFFER_FRAMES)
+    }
+
+    // Keeps presses for `frames` frames instead of BUFFER_FRAMES
+    pub fn with_frames(key: KeyCode, frames: u32) -> Self {
+        Self { key, frames, frames_left: 0 }
+    }
+
+    // Call once at the start of every frame
+    pub fn update(&mut self) {
+        if is_key_pressed(self.key) {
+            self.frames_left = self.frames;
+        } else {
+            self.frames_left = self.frames_left.saturating_sub(1);
+        }
+    }
+
+    // True if the key was pressed within the last few frames. Using the press clears it,
+    // so it can't also trigger whatever the next state does with the same key
+    pub fn consume(&mut self) -> bool {
+        let pressed = self.frames_left > 0;
+        self.frames_left = 0;
+        pressed
+    }
+
+    // The same as consume, but leaves the press there for later frames too
+    pub fn is_buffered(&self) -> bool {
+        self.frames_left > 0
+    }
+
+    pub fn clear(&mut self) {
+        self.frames_left = 0;
+    }
+}
+
+// Seconds allowed between the two presses of a double tap
+const DOUBLE_TAP_TIME: f64 = 0.25f64;
+
+// Spots a key being tapped twice in quick succession
+pub struct DoubleTap {
+    key: KeyCode,
+    last_press: f64,
+}
+
+impl DoubleTap {
+    pub fn new(key: KeyCode) -> Self {
+        Self {
+            key,
+            last_press: f64::NEG_INFINITY,
+        }
+    }
+
+    // Call once per frame. True on the frame the second tap lands, which also starts the count over
+    // so a third tap doesn't count as another double
+    pub fn update(&mut self) -> bool {
+        if !is_key_pressed(self.key) {
+            return false;
+        }
+        let now = get_time();
+        if now - self.last_press <= DOUBLE_TAP_TIME {
+            self.last_press = f64::NEG_INFINITY;
+            true
+        } else {
+            self.last_press = now;
+            false
+        }
+    }
+}
+
+// The system clipboard, which macroquad only wraps for its own UI. Both are only called from the
+// main loop, the same thread macroquad's UI reaches the context from
+pub fn clipboard_get() -> Option<String> {
+    unsafe { get_internal_gl() }.quad_context.clipboard_get()
+}
+
+pub fn clipboard_set(text: &str) {
+    unsafe { get_internal_gl() }.quad_context.clipboard_set(text);
+}
@@ -0,0 +1,92 @@
+use macroquad::prelude::*;
+
+use crate::lang::Lang;
+use crate::menu::Menu;
+use crate::draw_centered_text;
+
+// Something the score from the campaign so far can buy for the next level
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShopItem {
+    ExtraLife,
+    // A wider paddle for the whole of the next level
+    Enlarge,
+    // The safety net armed for the next level, even with its own setting off
+    Shield,
+}
+
+impl ShopItem {
+    pub const ALL: [ShopItem; 3] = [ShopItem::ExtraLife, ShopItem::Enlarge, ShopItem::Shield];
+
+    pub fn cost(self) -> i32 {
+        match self {
+            ShopItem::ExtraLife => 300,
+            ShopItem::Enlarge => 200,
+            ShopItem::Shield => 250,
+        }
+    }
+
+    pub fn text_id(self) -> &'static str {
+        match self {
+            ShopItem::ExtraLife => "shop.extra_life",
+            ShopItem::Enlarge => "shop.enlarge",
+            ShopItem::Shield => "shop.shield",
+        }
+    }
+}
+
+// What the main loop should do after an interlude frame
+pub enum InterludeAction {
+    // Starts the next level, with the item bought if there is one
+    Continue(Option<ShopItem>),
+    // Asks whether to give up the run and go back to the menu
+    Quit,
+}
+
+// Shown between campaign levels. One item can be bought with the score carried over, or the
+// shop skipped to spend nothing. The last entry is the skip, so an empty slot in the menu
+pub struct Interlude {
+    menu: Menu<Option<ShopItem>>,
+}
+
+impl Interlude {
+    pub fn new() -> Self {
+        let mut entries: Vec<Option<ShopItem>> = ShopItem::ALL.iter().copied().map(Some).collect();
+        entries.push(None);
+        Self {
+            menu: Menu::new(entries),
+        }
+    }
+
+    // Items the score can't cover are ignored when chosen
+    pub fn update(&mut self, score: i32, confirm: bool) -> Option<InterludeAction> {
+        match self.menu.handle_input(confirm || is_key_pressed(KeyCode::Enter)) {
+            Some(Some(item)) if item.cost() > score => None,
+            Some(choice) => Some(InterludeAction::Continue(choice)),
+            None if is_key_pressed(KeyCode::Escape) => Some(InterludeAction::Quit),
+            None => None,
+        }
+    }
+
+    pub fn draw(&self, score: i32, lang: &Lang, font: Font) {
+        draw_centered_text(lang.tr("shop.title"), 100f32, font);
+        draw_centered_text(&lang.tr("shop.score").replace("{n}", &score.to_string()), 150f32, font);
+        let labels: Vec<String> = self
+            .menu
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                Some(item) => {
+                    let label = format!("{} - {}", lang.tr(item.text_id()), item.cost());
+                    if item.cost() > score {
+                        format!("{} {}", label, lang.tr("shop.too_expensive"))
+                    } else {
+                        label
+                    }
+                },
+                None => lang.tr("shop.skip").to_string(),
+            })
+            .collect();
+        self.menu.draw(&labels, 230f32, 50f32, font);
+        draw_centered_text(lang.tr("shop.help"), screen_height() - 60f32, font);
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::assets::load_asset;
+
+// Language codes offered in settings with the name each one goes by in itself
+pub const LANGUAGES: [(&str, &str); 2] = [("en", "English"), ("es", "Español")];
+
+// The built-in strings, used whole for English and for any id a translation leaves out
+// Text in braces is a placeholder filled in by the caller
+const ENGLISH: &[(&str, &str)] = &[
+    ("title", "Breakout"),
+    ("menu.campaign", "Campaign"),
+    ("menu.level_select", "Level select"),
+    ("menu.practice", "Practice"),
+    ("menu.mutators", "Mutators"),
+    ("menu.editor", "Level editor"),
+    ("menu.customize", "Customize"),
+    ("menu.settings", "Settings"),
+    ("menu.stats", "Stats"),
+    ("menu.leaderboard", "Leaderboard"),
+    ("menu.quit", "Quit"),
+    ("menu.help", "UP/DOWN to choose, SPACE or ENTER to select, V to play a level code from the clipboard"),
+    ("settings.title", "Settings"),
+    ("settings.help", "UP/DOWN to select, LEFT/RIGHT to change, ESC to go back"),
+    ("settings.fps_cap", "FPS cap"),
+    ("settings.fullscreen", "Fullscreen (on restart)"),
+    ("settings.portals", "Portal blocks (next run)"),
+    ("settings.log_sessions", "Log sessions (next run)"),
+    ("settings.danger_line", "Danger line"),
+    ("settings.spawn_ball_blocks", "Spawn-ball blocks (next run)"),
+    ("settings.difficulty", "Difficulty"),
+    ("settings.forgiveness_floor", "Forgiveness floor (not on hard)"),
+    ("settings.language", "Language"),
+    ("settings.invert_controls", "Invert left/right"),
+    ("settings.music", "Music"),
+    ("settings.safety_net", "Safety net (easy, next run)"),
+    ("settings.reduced_motion", "Reduced motion and effects"),
+    ("settings.paddle_speed", "Paddle speed"),
+    ("settings.landing_marker", "Landing marker (assist)"),
+    ("settings.auto_aim", "Auto-aim (assist)"),
+    ("settings.assist", "Assist mode (next run)"),
+    ("settings.fixed_aspect", "Keep the playfield 16:9"),
+    ("settings.last_block_slow_motion", "Last block slow motion"),
+    ("settings.classic_movement", "Classic paddle movement"),
+    ("settings.paddle_tilt", "Tilt the paddle with Q/E"),
+    ("mutators.title", "Mutators"),
+    ("mutators.multiplier", "Score multiplier x{n}"),
+    ("mutators.help", "UP/DOWN to choose, SPACE or ENTER to toggle, ESC to go back"),
+    ("mutator.tiny", "Tiny paddle"),
+    ("mutator.fog", "Fog"),
+    ("mutator.turbo", "Turbo"),
+    ("mutator.memory", "Memory"),
+    ("mutator.wrap", "Open sides"),
+    ("shop.title", "Level cleared"),
+    ("shop.score", "Score to spend: {n}"),
+    ("shop.extra_life", "Extra life"),
+    ("shop.enlarge", "Start with a wider paddle"),
+    ("shop.shield", "Start with a shield"),
+    ("shop.too_expensive", "(not enough score)"),
+    ("shop.skip", "Skip"),
+    ("shop.help", "UP/DOWN to choose, SPACE or ENTER to buy and play on, ESC to quit"),
+    ("on", "on"),
+    ("off", "off"),
+    ("uncapped", "uncapped"),
+    ("easy", "easy"),
+    ("normal", "normal"),
+    ("hard", "hard"),
+    ("slow", "slow"),
+    ("fast", "fast"),
+    ("stats.title", "Stats"),
+    ("stats.games_played", "Games played"),
+    ("stats.best_score", "Best score"),
+    ("stats.total_score", "Total score"),
+    ("stats.blocks_destroyed", "Blocks destroyed"),
+    ("stats.play_time", "Play time"),
+    ("stats.longest_chain", "Longest chain"),
+    ("popup.double", "DOUBLE!"),
+    ("popup.triple", "TRIPLE!"),
+    ("popup.quad", "QUAD!"),
+    ("popup.chain", "CHAIN x{n}!"),
+    ("popup.points", "+{n}"),
+    ("popup.penalty", "-{n}"),
+    ("popup.code_copied", "Level code copied"),
+    ("announce.nice", "NICE!"),
+    ("announce.amazing", "AMAZING!"),
+    ("announce.incredible", "INCREDIBLE!"),
+    ("leaderboard.title", "Fastest clears"),
+    ("leaderboard.empty", "No clears yet"),
+    ("leaderboard.new_time", "New best time {time}! Enter your initials"),
+    ("leaderboard.entry_help", "UP/DOWN to change, LEFT/RIGHT to move, ENTER to save"),
+    ("leaderboard.assisted", "(assist)"),
+    ("leaderboard.continued", "(continued)"),
+    ("level_select.title", "Level select"),
+    ("level_select.level", "Level {n}"),
+    ("level_select.best", "Best {score} in {time}"),
+    ("level_select.not_cleared", "Not cleared yet"),
+    ("level_select.help", "Arrow keys to choose, SPACE to play, ESC to go back"),
+    ("customize.title", "Customize"),
+    ("customize.equipped", "Equipped"),
+    ("customize.equip", "Press SPACE to equip"),
+    ("customize.help", "UP/DOWN to choose, ESC to go back"),
+    ("skin.classic", "Classic"),
+    ("skin.ocean", "Ocean"),
+    ("skin.forest", "Forest"),
+    ("skin.ember", "Ember"),
+    ("skin.chain", "Chain reaction"),
+    ("skin.gold", "Gold"),
+    ("skin.unlock_score", "Locked: score {n} points in total"),
+    ("skin.unlock_blocks", "Locked: break {n} blocks in total"),
+    ("skin.unlock_chain", "Locked: break {n} blocks in one chain"),
+    ("editor.help", "Left click to place, right click to clear, scroll to change block, C to copy the level code, I or X to import or export level.txt, L to export a new level file, ESC to go back"),
+    ("editor.save", "Save"),
+    ("editor.test_play", "Test play"),
+    ("editor.saved", "Saved to {path}"),
+    ("editor.save_failed", "Couldn't save {path}"),
+    ("editor.code_copied", "Level code copied to the clipboard"),
+    ("editor.open_failed", "Couldn't open {path}"),
+    ("editor.exported", "Exported to {path}"),
+    ("editor.export_failed", "Couldn't write to {path}"),
+    ("editor.imported", "Imported {path}"),
+    ("editor.layout_unsupported", "Traps, portals and brittle blocks can't be exported as a classic layout"),
+    ("editor.layout_unknown_character", "{path}, line {line}, column {column}: not a block character"),
+    ("editor.layout_ragged_row", "{path}, line {line}, column {column}: every row must be as wide as the first"),
+    ("editor.layout_empty", "{path} has no rows"),
+    ("back", "ESC to go back"),
+    ("online.offline", "offline"),
+    ("online.top", "Global top 10"),
+    ("loading.progress", "{n} of {total}"),
+    ("loading.failed", "Couldn't load {path}"),
+    ("loading.continue", "Press SPACE to continue with fallbacks"),
+    ("hud.score", "Score: {score}"),
+    ("hud.lives", "Lives: {lives}"),
+    ("hud.safety_net", "Safety net ready"),
+    ("hud.assist", "Assist"),
+    ("hud.challenge", "Challenge level"),
+    ("hud.time_left", "Time left {time}"),
+    ("game.serve", "SPACE to serve"),
+    ("game.paused", "Paused"),
+    ("pause.resume", "Resume"),
+    ("pause.quit", "Quit to menu"),
+    ("game.won", "You won with a score of {score}!"),
+    ("game.lost", "You lost with a score of {score}!"),
+    ("game.cheated", " (cheats used)"),
+    ("game.retry", " R to retry the level"),
+    ("game.leave", "Space to go back"),
+    ("game.replay", "S to replay this board (seed {seed}), N for a new one"),
+    ("game.continue", "C to continue with half the score ({n} left)"),
+    ("game.next_level", "SPACE for the next level"),
+    ("prompt.quit", "Quit to menu? Your run will be lost — Y/N"),
+    ("prompt.exit", "Exit Breakout? Y/N"),
+    ("warning.font", "Couldn't load {path}, using the default font"),
+    ("warning.level_empty", "That level has no blocks, so it can't be played"),
+    ("warning.level_unwinnable", "That level has nothing to break, so it can't be won"),
+    ("warning.level_code_invalid", "The clipboard doesn't hold a valid level code"),
+    ("warning.level_modifiers", "That level's modifiers contradict each other, so it can't be played"),
+    ("warning.level_unreadable", "A level file couldn't be read"),
+    ("warning.level_newer_version", "A level file was saved by a newer version of the game, so it can't be loaded"),
+];
+
+// On-screen text for one language, loaded from res/lang/<code>.json as a flat object of id to string
+pub struct Lang {
+    strings: HashMap<String, String>,
+}
+
+impl Lang {
+    pub fn english() -> Self {
+        Self {
+            strings: HashMap::new(),
+        }
+    }
+
+    // A missing or malformed file leaves everything in English
+    pub async fn load(code: &str) -> Self {
+        if code == "en" {
+            return Self::english();
+        }
+        let strings = load_asset(&format!("res/lang/{}.json", code))
+            .await
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .and_then(|text| parse_strings(&text))
+            .unwrap_or_default();
+        Self { strings }
+    }
+
+    // Looks up the text for an id, falling back to English, then to the id itself so values
+    // with nothing to translate like "60" pass straight through
+    pub fn tr<'a>(&'a self, id: &'a str) -> &'a str {
+        if let Some(text) = self.strings.get(id) {
+            return text;
+        }
+        ENGLISH
+            .iter()
+            .find(|(english_id, _)| *english_id == id)
+            .map_or(id, |(_, text)| text)
+    }
+}
+
+// Reads a JSON object whose values are all strings. Returns None if the text isn't one
+fn parse_strings(text: &str) -> Option<HashMap<String, String>> {
+    let mut chars = text.chars().peekable();
+    let mut strings = HashMap::new();
+
+    skip_whitespace(&mut chars);
+    if chars.next()? != '{' {
+        return None;
+    }
+    loop {
+        skip_whitespace(&mut chars);
+        match chars.peek()? {
+            '}' => break,
+            ',' => {
+                chars.next();
+                continue;
+            },
+            _ => {},
+        }
+        let key = parse_string(&mut chars)?;
+        skip_whitespace(&mut chars);
+        if chars.next()? != ':' {
+            return None;
+        }
+        skip_whitespace(&mut chars);
+        let value = parse_string(&mut chars)?;
+        strings.insert(key, value);
+    }
+    Some(strings)
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|character| character.is_whitespace()).is_some() {}
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Option<String> {
+    if chars.next()? != '"' {
+        return None;
+    }
+    let mut string = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(string),
+            '\\' => match chars.next()? {
+                'n' => string.push('\n'),
+                't' => string.push('\t'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    string.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                },
+                character => string.push(character),
+            },
+            character => string.push(character),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use macroquad::prelude::*;
+
+use crate::mutators::Mutator;
+
+pub const LEADERBOARD_PATH: &str = "leaderboard.txt";
+const MAX_ENTRIES: usize = 5;
+
+pub struct LeaderboardEntry {
+    pub initials: String,
+    pub time: f32,
+    // Set in assist mode
+    pub assisted: bool,
+    // Set if the run carried on from a game over
+    pub continued: bool,
+    pub mutators: Vec<Mutator>,
+}
+
+// Fastest full clears, one `INI seconds` line per entry so a damaged file only loses the lines that don't parse.
+// After the time come `assist` for clears made in assist mode, `continued` for runs that were continued, and
+// the names of any mutators
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    pub fn load() -> Self {
+        let mut leaderboard = Self { entries: Vec::new() };
+        if let Ok(text) = std::fs::read_to_string(LEADERBOARD_PATH) {
+            for line in text.lines() {
+                let mut fields = line.split_whitespace();
+                let (Some(initials), Some(time)) = (fields.next(), fields.next()) else {
+                    continue;
+                };
+                let Ok(time) = time.parse::<f32>() else {
+                    continue;
+                };
+                let mut fields = fields.peekable();
+                let assisted = fields.next_if_eq(&"assist").is_some();
+                let continued = fields.next_if_eq(&"continued").is_some();
+                let Some(mutators) = fields.map(Mutator::from_name).collect::<Option<Vec<_>>>() else {
+                    continue;
+                };
+                if initials.len() == 3 && initials.chars().all(|character| character.is_ascii_uppercase()) && time > 0f32
+                {
+                    leaderboard.entries.push(LeaderboardEntry {
+                        initials: initials.to_string(),
+                        time,
+                        assisted,
+                        continued,
+                        mutators,
+                    });
+                }
+            }
+        }
+        leaderboard.sort();
+        leaderboard
+    }
+
+    // Best effort like the other save files
+    pub fn save(&self) {
+        let text: String = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut line = format!("{} {:.3}", entry.initials, entry.time);
+                if entry.assisted {
+                    line.push_str(" assist");
+                }
+                if entry.continued {
+                    line.push_str(" continued");
+                }
+                for mutator in entry.mutators.iter() {
+                    line.push(' ');
+                    line.push_str(mutator.name());
+                }
+                line + "\n"
+            })
+            .collect();
+        let _ = std::fs::write(LEADERBOARD_PATH, text);
+    }
+
+    // Whether a clear in this many seconds would make the board
+    pub fn qualifies(&self, time: f32) -> bool {
+        self.entries.len() < MAX_ENTRIES || self.entries.iter().any(|entry| time < entry.time)
+    }
+
+    pub fn insert(&mut self, entry: LeaderboardEntry) {
+        self.entries.push(entry);
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        self.entries.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.entries.truncate(MAX_ENTRIES);
+    }
+}
+
+// Minutes, seconds, and hundredths, like 1:23.45
+pub fn format_time(seconds: f32) -> String {
+    let hundredths = (seconds * 100f32).round() as u32;
+    format!("{}:{:02}.{:02}", hundredths / 6000, hundredths / 100 % 60, hundredths % 100)
+}
+
+// Arcade-style initials picker: UP/DOWN change the letter under the cursor, LEFT/RIGHT move it
+pub struct InitialsEntry {
+    pub letters: [u8; 3],
+    pub cursor: usize,
+}
+
+impl InitialsEntry {
+    // Starts from the last initials used so a returning player can just confirm
+    pub fn new(initials: &str) -> Self {
+        let mut letters = *b"AAA";
+        for (letter, character) in letters.iter_mut().zip(initials.bytes()) {
+            if character.is_ascii_uppercase() {
+                *letter = character;
+            }
+        }
+        Self { letters, cursor: 0 }
+    }
+
+    // Returns true once ENTER confirms the initials
+    pub fn update(&mut self) -> bool {
+        let letter = &mut self.letters[self.cursor];
+        if is_key_pressed(KeyCode::Up) {
+            *letter = if *letter == b'Z' { b'A' } else { *letter + 1 };
+        } else if is_key_pressed(KeyCode::Down) {
+            *letter = if *letter == b'A' { b'Z' } else { *letter - 1 };
+        } else if is_key_pressed(KeyCode::Left) {
+            self.cursor = (self.cursor + self.letters.len() - 1) % self.letters.len();
+        } else if is_key_pressed(KeyCode::Right) {
+            self.cursor = (self.cursor + 1) % self.letters.len();
+        }
+        is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter)
+    }
+
+    pub fn initials(&self) -> String {
+        String::from_utf8_lossy(&self.letters).into_owned()
+    }
+}
@@ -0,0 +1,649 @@
+use macroquad::prelude::*;
+
+use crate::assets::load_asset;
+use crate::{Block, BlockType, BoardLayout, Field, BLOCK_PADDING, BLOCK_SIZE};
+
+// Where the editor saves and loads its layout
+pub const EDITOR_LEVEL_PATH: &str = "level.lvl";
+// Where exported boards are written, one new file each
+pub const EXPORTS_DIR: &str = "exports";
+// How far off its grid cell, as a fraction of the cell, a block can be before snapping it counts as moving it
+const SNAP_TOLERANCE: f32 = 0.05f32;
+// The campaign, in play order
+pub const CAMPAIGN_LEVELS: [&str; 6] = [
+    "res/levels/01.lvl",
+    "res/levels/02.lvl",
+    "res/levels/03.lvl",
+    "res/levels/04.lvl",
+    "res/levels/05.lvl",
+    "res/levels/06.lvl",
+];
+// Widest gap between blocks a level file can ask for, in pixels at full size. Anything wider, or not a
+// finite number, makes the file unreadable rather than laying the board out at a scale of nothing
+const MAX_PADDING: f32 = 200f32;
+// The same for the strongest gravity and the longest time limit a file can set. Past these the pull would
+// spin the ball's heading around faster than it can be drawn, or the clock would never be seen running out
+const MAX_GRAVITY: f32 = 20f32;
+const MAX_TIMED: f32 = 3600f32;
+// First byte of every level code, to be bumped if the layout after it ever changes
+const LEVEL_CODE_VERSION: u8 = 1;
+// Written as the first line of every saved level. A file without one is from before files were
+// versioned, which is version 1. A format change bumps this, keeps the old layout as its own LevelV
+// struct with a From conversion up to the next, and adds it to Level::parse, so old files keep loading
+// the way they were written. Adding a cell character counts as a change too, since an older build can't
+// read the new one
+const LEVEL_FORMAT_VERSION: u32 = 3;
+// Column, row, type, lives, and starting lives
+const LEVEL_CODE_BLOCK_BYTES: usize = 5;
+// Portals are stored as this plus their pair id
+const LEVEL_CODE_PORTAL: u8 = 16;
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Why a level can't be played
+#[derive(Clone, Copy)]
+pub enum LevelError {
+    // Not a single block
+    Empty,
+    // Blocks, but none that can be broken, so the level could never be won
+    NoDestructibleBlocks,
+    // A pasted level code that doesn't decode
+    InvalidCode,
+    // Level modifiers that can't all hold at once, or that make no sense for the board
+    ContradictoryModifiers,
+    // A level file with an unknown character or setting, or no rows
+    Unreadable,
+    // A level file saved by a newer version of the game, in a format this one doesn't know
+    NewerVersion,
+}
+
+impl LevelError {
+    pub fn text_id(self) -> &'static str {
+        match self {
+            LevelError::Empty => "warning.level_empty",
+            LevelError::NoDestructibleBlocks => "warning.level_unwinnable",
+            LevelError::InvalidCode => "warning.level_code_invalid",
+            LevelError::ContradictoryModifiers => "warning.level_modifiers",
+            LevelError::Unreadable => "warning.level_unreadable",
+            LevelError::NewerVersion => "warning.level_newer_version",
+        }
+    }
+}
+
+// A hand-made board, stored as plain text with one line per row and one character per cell:
+// `.` or a space empty, `R` regular, `W` a regular block that starts a hit down, `S` spawn-ball, `E` explosive, `I` indestructible, `T` trap, `B` brittle, and a digit
+// for a portal with that pair id. Lines starting with # are skipped, and so are blank lines before the
+// first row and after the last. A blank line between rows is a row of empty cells.
+// Lines starting with a lowercase word set the star thresholds: `par 30` for the par time in seconds
+// and `max_lives_lost 1` for how many lives a flawless clear can still lose. The same kind of line sets
+// the level's modifiers: `no_paddle_aim`, `darkness`, `timed 45` up to MAX_TIMED, `gravity 0.8` up to
+// MAX_GRAVITY and `max_balls 2`, and
+// `padding 12` sets the gap between blocks in pixels at full size, from 0 to MAX_PADDING, BLOCK_PADDING
+// without one.
+// `version 1` says which layout of the format the file was written in
+#[derive(Clone)]
+pub struct Level {
+    pub columns: usize,
+    // Row by row, top first
+    pub cells: Vec<Option<BlockType>>,
+    // Cells whose block starts with one life instead of two, always false for an empty cell
+    pub worn: Vec<bool>,
+    // Without a par time the second star comes with any clear
+    pub par_time: Option<f32>,
+    pub max_lives_lost: u32,
+    pub modifiers: LevelModifiers,
+    pub padding: f32,
+}
+
+// Challenge rules a level can set for itself. A level with any of them plays without the player's
+// own mutators, so the challenge is the same for everyone
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct LevelModifiers {
+    // Paddle bounces reflect like a wall instead of aiming by where the ball lands
+    pub no_paddle_aim: bool,
+    // The Fog mutator, forced on
+    pub darkness: bool,
+    // Seconds to clear the level in, after which the run is lost
+    pub timed: Option<f32>,
+    // How fast a ball's heading turns downwards, in units of its speed per second
+    pub gravity: Option<f32>,
+    // Fewer balls than MAX_BALLS can be in play at once
+    pub max_balls: Option<usize>,
+}
+
+impl LevelModifiers {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // Whether the modifiers can hold together on this level. A par time the clock runs out before,
+    // a zero, or spawn-ball blocks on a level that only allows one ball are refused
+    fn validate(&self, level: &Level) -> Result<(), LevelError> {
+        let timed_ok = self.timed.is_none_or(|limit| limit > 0f32 && level.par_time.is_none_or(|par| par < limit));
+        let gravity_ok = self.gravity.is_none_or(|gravity| gravity > 0f32);
+        let has_spawn_blocks = level.cells.contains(&Some(BlockType::SpawnBallOnDeath));
+        let max_balls_ok = self.max_balls.is_none_or(|max| max > 1 || (max == 1 && !has_spawn_blocks));
+        if timed_ok && gravity_ok && max_balls_ok {
+            Ok(())
+        } else {
+            Err(LevelError::ContradictoryModifiers)
+        }
+    }
+}
+
+impl Level {
+    pub fn empty(columns: usize, rows: usize) -> Self {
+        Self {
+            columns,
+            cells: vec![None; columns * rows],
+            worn: vec![false; columns * rows],
+            par_time: None,
+            max_lives_lost: 0,
+            modifiers: LevelModifiers::default(),
+            padding: BLOCK_PADDING,
+        }
+    }
+
+    // One star for the clear, one for beating the par time, and one for keeping to the lives limit
+    pub fn stars(&self, time: f32, lives_lost: u32) -> u8 {
+        let under_par = self.par_time.is_none_or(|par| time <= par);
+        1 + under_par as u8 + (lives_lost <= self.max_lives_lost) as u8
+    }
+
+    pub fn rows(&self) -> usize {
+        self.cells.len() / self.columns
+    }
+
+    pub fn cell(&self, column: usize, row: usize) -> Option<BlockType> {
+        self.cells[row * self.columns + column]
+    }
+
+    // Clears the cell's wear along with whatever was in it
+    pub fn set_cell(&mut self, column: usize, row: usize, cell: Option<BlockType>) {
+        self.cells[row * self.columns + column] = cell;
+        self.worn[row * self.columns + column] = false;
+    }
+
+    pub fn is_worn(&self, column: usize, row: usize) -> bool {
+        self.worn[row * self.columns + column]
+    }
+
+    pub fn set_worn(&mut self, column: usize, row: usize, worn: bool) {
+        self.worn[row * self.columns + column] = worn;
+    }
+
+    // A file is read as the version it says it's in, so it can only use what that version had, then
+    // converted up to the current one. Newer ones are refused rather than guessed at, since a setting
+    // this version doesn't know could change how the board plays
+    pub fn parse(text: &str) -> Result<Self, LevelError> {
+        let version = format_version(text).ok_or(LevelError::Unreadable)?;
+        let level = match version {
+            1 => LevelV1::parse(text).map(|level| Self::from(LevelV2::from(level))),
+            2 => LevelV2::parse(text).map(Self::from),
+            LEVEL_FORMAT_VERSION => Self::parse_current(text),
+            _ => return Err(LevelError::NewerVersion),
+        };
+        level.ok_or(LevelError::Unreadable)
+    }
+
+    // The current version, which has every cell character
+    fn parse_current(text: &str) -> Option<Self> {
+        let (settings, rows) = read_lines(text)?;
+        let (columns, cells) = read_cells(&rows, |_| true)?;
+        Some(Self {
+            columns,
+            worn: read_worn(&rows, columns),
+            cells,
+            par_time: settings.par_time,
+            max_lives_lost: settings.max_lives_lost,
+            modifiers: settings.modifiers,
+            padding: settings.padding,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("version {}\n", LEVEL_FORMAT_VERSION);
+        if let Some(par) = self.par_time {
+            text.push_str(&format!("par {}\n", par));
+        }
+        if self.max_lives_lost > 0 {
+            text.push_str(&format!("max_lives_lost {}\n", self.max_lives_lost));
+        }
+        if self.modifiers.no_paddle_aim {
+            text.push_str("no_paddle_aim\n");
+        }
+        if self.modifiers.darkness {
+            text.push_str("darkness\n");
+        }
+        if let Some(seconds) = self.modifiers.timed {
+            text.push_str(&format!("timed {}\n", seconds));
+        }
+        if let Some(strength) = self.modifiers.gravity {
+            text.push_str(&format!("gravity {}\n", strength));
+        }
+        if let Some(count) = self.modifiers.max_balls {
+            text.push_str(&format!("max_balls {}\n", count));
+        }
+        if self.padding != BLOCK_PADDING {
+            text.push_str(&format!("padding {}\n", self.padding));
+        }
+        for row in 0..self.rows() {
+            text.extend((0..self.columns).map(|column| match self.is_worn(column, row) {
+                true => 'W',
+                false => cell_char(self.cell(column, row)),
+            }));
+            text.push('\n');
+        }
+        text
+    }
+
+    // Turns a board back into a level, each block snapped to the grid it was laid out on with empty rows
+    // and columns around the edge trimmed. Also returns how many blocks had to be moved to fit it: ones
+    // that had drifted off their cell, and ones that landed on a cell already taken and were left out
+    pub fn from_blocks(blocks: &[Block], padding: f32) -> (Self, usize) {
+        let cells = snap_to_grid(blocks, padding);
+        let columns = cells.iter().map(|&(column, _, _)| column + 1).max().unwrap_or(1);
+        let rows = cells.iter().map(|&(_, row, _)| row + 1).max().unwrap_or(1);
+        let mut level = Self::empty(columns, rows);
+        level.padding = padding;
+        let mut snapped = 0;
+        for (block, (column, row, moved)) in blocks.iter().zip(cells) {
+            if level.cell(column, row).is_some() {
+                snapped += 1;
+                continue;
+            }
+            snapped += moved as usize;
+            level.set_cell(column, row, Some(block.block_type));
+            level.set_worn(column, row, block.block_type == BlockType::Regular && block.lives < block.max_lives);
+        }
+        (level, snapped)
+    }
+
+    // Writes the level as a new file in EXPORTS_DIR, returning its path
+    pub fn export(&self) -> std::io::Result<String> {
+        std::fs::create_dir_all(EXPORTS_DIR)?;
+        let path = format!("{}/{}.lvl", EXPORTS_DIR, (miniquad::date::now() * 1000f64) as u64);
+        std::fs::write(&path, self.to_text())?;
+        Ok(path)
+    }
+
+    // Lays the level out with the same centering and scaling as a generated board, placing each
+    // block at its own grid coordinate so empty cells stay empty
+    pub fn blocks(&self) -> Vec<Block> {
+        self.blocks_in_field(Field::full())
+    }
+
+    // The same, centered between the walls of a narrower field
+    pub fn blocks_in_field(&self, field: Field) -> Vec<Block> {
+        self.blocks_with(BoardLayout::fit_field(self.columns, field, self.padding))
+    }
+
+    // The blocks to start a run with, refusing a board that would be won the moment it started
+    // because there's nothing on it to break, or one whose modifiers contradict each other
+    pub fn playable_blocks(&self) -> Result<Vec<Block>, LevelError> {
+        self.playable_blocks_in_field(Field::full())
+    }
+
+    // The same, laid out between the walls of a narrower field
+    pub fn playable_blocks_in_field(&self, field: Field) -> Result<Vec<Block>, LevelError> {
+        self.modifiers.validate(self)?;
+        check_playable(self.blocks_in_field(field))
+    }
+
+    // The same board shrunk into an area, for previews
+    pub fn blocks_in(&self, area: Rect) -> Vec<Block> {
+        self.blocks_with(BoardLayout::fit_in(self.columns, self.rows(), area, self.padding))
+    }
+
+    fn blocks_with(&self, layout: BoardLayout) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        for row in 0..self.rows() {
+            for column in 0..self.columns {
+                if let Some(block_type) = self.cell(column, row) {
+                    let mut block = layout.block(column, row, block_type);
+                    if self.is_worn(column, row) {
+                        block.lives = 1;
+                    }
+                    blocks.push(block);
+                }
+            }
+        }
+        blocks
+    }
+}
+
+fn check_playable(blocks: Vec<Block>) -> Result<Vec<Block>, LevelError> {
+    if blocks.is_empty() {
+        Err(LevelError::Empty)
+    } else if !blocks.iter().any(|block| block.is_destructible()) {
+        Err(LevelError::NoDestructibleBlocks)
+    } else {
+        Ok(blocks)
+    }
+}
+
+// Packs a board, damage included, into a base64 string that can be shared and pasted back in: a version
+// byte, then LEVEL_CODE_BLOCK_BYTES per block. Blocks are snapped back to the grid they were laid out on,
+// trimming empty rows and columns around the edge, so a code doesn't depend on the window size. `padding`
+// is the gap the board was laid out with. Codes don't store it, so they always decode at BLOCK_PADDING
+pub fn encode_level(blocks: &[Block], padding: f32) -> String {
+    let mut bytes = vec![LEVEL_CODE_VERSION];
+    for (block, (column, row, _)) in blocks.iter().zip(snap_to_grid(blocks, padding)) {
+        bytes.extend([
+            column.min(255) as u8,
+            row.min(255) as u8,
+            type_code(block.block_type),
+            block.lives.clamp(1, 255) as u8,
+            block.max_lives.clamp(1, 255) as u8,
+        ]);
+    }
+    to_base64(&bytes)
+}
+
+// The column and row of each block's nearest grid cell, counted from the top left block, and whether it
+// was further off that cell than SNAP_TOLERANCE. The grid's scale comes from the first block's size
+fn snap_to_grid(blocks: &[Block], padding: f32) -> Vec<(usize, usize, bool)> {
+    let Some(first) = blocks.first() else {
+        return Vec::new();
+    };
+    let cell_size = (BLOCK_SIZE + vec2(padding, padding)) * (first.rect.w / BLOCK_SIZE.x);
+    let min_x = blocks.iter().map(|block| block.rect.x).fold(f32::INFINITY, f32::min);
+    let min_y = blocks.iter().map(|block| block.rect.y).fold(f32::INFINITY, f32::min);
+    blocks
+        .iter()
+        .map(|block| {
+            let cell = (block.rect.point() - vec2(min_x, min_y)) / cell_size;
+            let snapped = cell.round();
+            let moved = (cell - snapped).abs().max_element() > SNAP_TOLERANCE;
+            (snapped.x as usize, snapped.y as usize, moved)
+        })
+        .collect()
+}
+
+// Lays a pasted code out like a level file with the same blocks. Anything that doesn't decode is an
+// InvalidCode error rather than a panic, and a board that couldn't be won is refused like a level would be
+pub fn decode_level(code: &str) -> Result<Vec<Block>, LevelError> {
+    decode_level_in(code, Field::full())
+}
+
+pub fn decode_level_in(code: &str, field: Field) -> Result<Vec<Block>, LevelError> {
+    let bytes = from_base64(code).ok_or(LevelError::InvalidCode)?;
+    let (&version, cells) = bytes.split_first().ok_or(LevelError::InvalidCode)?;
+    if version != LEVEL_CODE_VERSION || !cells.len().is_multiple_of(LEVEL_CODE_BLOCK_BYTES) {
+        return Err(LevelError::InvalidCode);
+    }
+
+    let cells = cells.chunks_exact(LEVEL_CODE_BLOCK_BYTES);
+    let columns = cells.clone().map(|cell| cell[0] as usize + 1).max().unwrap_or(1);
+    let layout = BoardLayout::fit_field(columns, field, BLOCK_PADDING);
+    let mut blocks = Vec::new();
+    for cell in cells {
+        let block_type = type_from_code(cell[2]).ok_or(LevelError::InvalidCode)?;
+        let (lives, max_lives) = (cell[3] as i32, cell[4] as i32);
+        if lives == 0 || lives > max_lives {
+            return Err(LevelError::InvalidCode);
+        }
+        let mut block = layout.block(cell[0] as usize, cell[1] as usize, block_type);
+        block.lives = lives;
+        block.max_lives = max_lives;
+        blocks.push(block);
+    }
+    check_playable(blocks)
+}
+
+fn type_code(block_type: BlockType) -> u8 {
+    match block_type {
+        BlockType::Regular => 0,
+        BlockType::SpawnBallOnDeath => 1,
+        BlockType::Explosive => 2,
+        BlockType::Indestructible => 3,
+        BlockType::Trap => 4,
+        BlockType::Brittle => 5,
+        BlockType::Portal(pair) => LEVEL_CODE_PORTAL.saturating_add(pair),
+    }
+}
+
+fn type_from_code(code: u8) -> Option<BlockType> {
+    let block_type = match code {
+        0 => BlockType::Regular,
+        1 => BlockType::SpawnBallOnDeath,
+        2 => BlockType::Explosive,
+        3 => BlockType::Indestructible,
+        4 => BlockType::Trap,
+        5 => BlockType::Brittle,
+        LEVEL_CODE_PORTAL.. => BlockType::Portal(code - LEVEL_CODE_PORTAL),
+        _ => return None,
+    };
+    Some(block_type)
+}
+
+// Unpadded, since the length alone says how many bytes the last group holds
+fn to_base64(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| group | ((*byte as u32) << (16 - 8 * i)));
+        for i in 0..=chunk.len() {
+            text.push(BASE64_ALPHABET[((group >> (18 - 6 * i)) & 63) as usize] as char);
+        }
+    }
+    text
+}
+
+// Skips padding and whitespace, so a code still reads after being wrapped or padded on its way
+// through a chat message
+fn from_base64(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u32> = text
+        .chars()
+        .filter(|character| !character.is_whitespace() && *character != '=')
+        .map(|character| BASE64_ALPHABET.iter().position(|digit| *digit as char == character).map(|digit| digit as u32))
+        .collect::<Option<_>>()?;
+    if digits.len() % 4 == 1 {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    for chunk in digits.chunks(4) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, digit)| group | (digit << (18 - 6 * i)));
+        for i in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+// Loads every campaign level that exists and parses, in order, along with why the first one that
+// exists but couldn't be read was left out
+pub async fn load_campaign() -> (Vec<Level>, Option<LevelError>) {
+    let (mut levels, mut skipped) = (Vec::new(), None);
+    for path in CAMPAIGN_LEVELS.iter() {
+        let Some(bytes) = load_asset(path).await else {
+            continue;
+        };
+        match String::from_utf8(bytes).map_err(|_| LevelError::Unreadable).and_then(|text| Level::parse(&text)) {
+            Ok(level) => levels.push(level),
+            Err(error) => {
+                skipped.get_or_insert(error);
+            },
+        }
+    }
+    (levels, skipped)
+}
+
+// The setting lines, which every version of the format reads the same way
+#[derive(Clone, Copy)]
+struct LevelSettings {
+    par_time: Option<f32>,
+    max_lives_lost: u32,
+    modifiers: LevelModifiers,
+    padding: f32,
+}
+
+// A level as version 1 wrote it, from before files were versioned: no worn or brittle cells
+struct LevelV1 {
+    settings: LevelSettings,
+    columns: usize,
+    cells: Vec<Option<BlockType>>,
+}
+
+impl LevelV1 {
+    fn parse(text: &str) -> Option<Self> {
+        let (settings, rows) = read_lines(text)?;
+        let (columns, cells) = read_cells(&rows, |character| !matches!(character, 'W' | 'B'))?;
+        Some(Self { settings, columns, cells })
+    }
+}
+
+// Version 2 added `W` for a regular block that starts a hit down
+struct LevelV2 {
+    settings: LevelSettings,
+    columns: usize,
+    cells: Vec<Option<BlockType>>,
+    worn: Vec<bool>,
+}
+
+impl LevelV2 {
+    fn parse(text: &str) -> Option<Self> {
+        let (settings, rows) = read_lines(text)?;
+        let (columns, cells) = read_cells(&rows, |character| character != 'B')?;
+        Some(Self {
+            settings,
+            columns,
+            worn: read_worn(&rows, columns),
+            cells,
+        })
+    }
+}
+
+impl From<LevelV1> for LevelV2 {
+    fn from(level: LevelV1) -> Self {
+        Self {
+            settings: level.settings,
+            columns: level.columns,
+            worn: vec![false; level.cells.len()],
+            cells: level.cells,
+        }
+    }
+}
+
+// Version 3 added `B` for a brittle block, and is the current one read straight into Level
+impl From<LevelV2> for Level {
+    fn from(level: LevelV2) -> Self {
+        Self {
+            columns: level.columns,
+            cells: level.cells,
+            worn: level.worn,
+            par_time: level.settings.par_time,
+            max_lives_lost: level.settings.max_lives_lost,
+            modifiers: level.settings.modifiers,
+            padding: level.settings.padding,
+        }
+    }
+}
+
+// Splits a file into its settings and its rows, from the first row with a cell in it to the last.
+// None for an unknown setting, or a level with no rows
+fn read_lines(text: &str) -> Option<(LevelSettings, Vec<&str>)> {
+    let mut settings = LevelSettings {
+        par_time: None,
+        max_lives_lost: 0,
+        modifiers: LevelModifiers::default(),
+        padding: BLOCK_PADDING,
+    };
+    let modifiers = &mut settings.modifiers;
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        // Rows never contain lowercase letters, so a setting can't be mistaken for one
+        if trimmed.starts_with(|character: char| character.is_ascii_lowercase()) {
+            match trimmed.split_whitespace().collect::<Vec<&str>>().as_slice() {
+                // Already checked by parse
+                ["version", _] => {},
+                ["par", seconds] => settings.par_time = Some(seconds.parse().ok()?),
+                ["max_lives_lost", count] => settings.max_lives_lost = count.parse().ok()?,
+                ["no_paddle_aim"] => modifiers.no_paddle_aim = true,
+                ["darkness"] => modifiers.darkness = true,
+                // Zero and below are left for validate to refuse as contradictory
+                ["timed", seconds] => modifiers.timed = Some(seconds.parse().ok().filter(|seconds: &f32| seconds.is_finite() && *seconds <= MAX_TIMED)?),
+                ["gravity", strength] => modifiers.gravity = Some(strength.parse().ok().filter(|strength: &f32| strength.is_finite() && *strength <= MAX_GRAVITY)?),
+                ["max_balls", count] => modifiers.max_balls = Some(count.parse().ok()?),
+                ["padding", pixels] => settings.padding = pixels.parse().ok().filter(|pixels: &f32| (0f32..=MAX_PADDING).contains(pixels))?,
+                _ => return None,
+            }
+            continue;
+        }
+        lines.push(line.trim_end());
+    }
+    let first = lines.iter().position(|line| !line.is_empty())?;
+    let last = lines.iter().rposition(|line| !line.is_empty())?;
+    Some((settings, lines[first..=last].to_vec()))
+}
+
+// Every character keeps its column and every line its row, so holes never shift the blocks after
+// them. Short rows are padded with empty cells. Returns the column count and the cells row by row, or
+// None for a character the file's version doesn't have
+fn read_cells(rows: &[&str], has_character: impl Fn(char) -> bool) -> Option<(usize, Vec<Option<BlockType>>)> {
+    let columns = rows.iter().map(|row| row.chars().count()).max()?;
+    let mut cells = vec![None; columns * rows.len()];
+    for (row, line) in rows.iter().enumerate() {
+        for (column, character) in line.chars().enumerate() {
+            if !has_character(character) {
+                return None;
+            }
+            cells[row * columns + column] = cell_from_char(character)?;
+        }
+    }
+    Some((columns, cells))
+}
+
+// Which cells are written as `W`, laid out the same way as read_cells
+fn read_worn(rows: &[&str], columns: usize) -> Vec<bool> {
+    let mut worn = vec![false; columns * rows.len()];
+    for (row, line) in rows.iter().enumerate() {
+        for (column, character) in line.chars().enumerate() {
+            worn[row * columns + column] = character == 'W';
+        }
+    }
+    worn
+}
+
+// The version named on a file's `version` line, or 1 if it has none. None if the line doesn't hold a
+// version number
+fn format_version(text: &str) -> Option<u32> {
+    let line = text.lines().map(str::split_whitespace).find_map(|mut words| match words.next() {
+        Some("version") => Some(words.collect::<Vec<&str>>()),
+        _ => None,
+    });
+    match line.as_deref() {
+        None => Some(1),
+        Some([version]) => version.parse().ok().filter(|version| *version >= 1),
+        Some(_) => None,
+    }
+}
+
+fn cell_char(cell: Option<BlockType>) -> char {
+    match cell {
+        None => '.',
+        Some(BlockType::Regular) => 'R',
+        Some(BlockType::SpawnBallOnDeath) => 'S',
+        Some(BlockType::Explosive) => 'E',
+        Some(BlockType::Indestructible) => 'I',
+        Some(BlockType::Trap) => 'T',
+        Some(BlockType::Brittle) => 'B',
+        Some(BlockType::Portal(pair)) => char::from_digit(pair as u32 % 10, 10).unwrap_or('0'),
+    }
+}
+
+fn cell_from_char(character: char) -> Option<Option<BlockType>> {
+    let cell = match character {
+        '.' | ' ' => None,
+        'R' | 'W' => Some(BlockType::Regular),
+        'S' => Some(BlockType::SpawnBallOnDeath),
+        'E' => Some(BlockType::Explosive),
+        'I' => Some(BlockType::Indestructible),
+        'T' => Some(BlockType::Trap),
+        'B' => Some(BlockType::Brittle),
+        _ => Some(BlockType::Portal(character.to_digit(10)? as u8)),
+    };
+    Some(cell)
+}
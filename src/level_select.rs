@@ -0,0 +1,189 @@
+use macroquad::prelude::*;
+
+use crate::lang::Lang;
+use crate::leaderboard::format_time;
+use crate::level::{Level, LevelModifiers};
+use crate::progress::Progress;
+use crate::{draw_centered_text, draw_star};
+
+const GRID_COLUMNS: usize = 3;
+const TILE_SIZE: Vec2 = const_vec2!([300f32, 180f32]);
+const TILE_SPACING: f32 = 20f32;
+const GRID_TOP: f32 = 140f32;
+const THUMBNAIL_HEIGHT: f32 = 80f32;
+const LOCKED_COLOR: Color = Color::new(0.3f32, 0.3f32, 0.3f32, 1f32);
+const TILE_STAR_RADIUS: f32 = 9f32;
+// Modifier icons run along the top of a tile between its title and its stars
+const ICON_LEFT: f32 = 110f32;
+const ICON_SIZE: f32 = 16f32;
+const ICON_SPACING: f32 = 22f32;
+const ICON_COLOR: Color = ORANGE;
+
+// What the main loop should do after a level select frame
+pub enum LevelSelectAction {
+    Play(usize),
+    Back,
+}
+
+// The campaign levels in a grid of tiles, each showing a preview of its board and the best clear so far
+pub struct LevelSelect {
+    pub selected: usize,
+}
+
+impl LevelSelect {
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+
+    // Arrow keys move around the grid, stopping at its edges. Locked levels can be highlighted but not started
+    pub fn update(&mut self, levels: &[Level], progress: &Progress, confirm: bool) -> Option<LevelSelectAction> {
+        let count = levels.len();
+        if is_key_pressed(KeyCode::Left) && self.selected > 0 {
+            self.selected -= 1;
+        } else if is_key_pressed(KeyCode::Right) && self.selected + 1 < count {
+            self.selected += 1;
+        } else if is_key_pressed(KeyCode::Up) && self.selected >= GRID_COLUMNS {
+            self.selected -= GRID_COLUMNS;
+        } else if is_key_pressed(KeyCode::Down) && self.selected + GRID_COLUMNS < count {
+            self.selected += GRID_COLUMNS;
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            return Some(LevelSelectAction::Back);
+        }
+        if (confirm || is_key_pressed(KeyCode::Enter)) && self.selected < count && progress.is_unlocked(self.selected) {
+            return Some(LevelSelectAction::Play(self.selected));
+        }
+        None
+    }
+
+    pub fn draw(&self, levels: &[Level], progress: &Progress, lang: &Lang, font: Font) {
+        draw_centered_text(lang.tr("level_select.title"), 100f32, font);
+
+        for (i, level) in levels.iter().enumerate() {
+            let tile = tile_rect(i);
+            let unlocked = progress.is_unlocked(i);
+            let border = if i == self.selected {
+                YELLOW
+            } else if unlocked {
+                WHITE
+            } else {
+                LOCKED_COLOR
+            };
+            draw_rectangle_lines(tile.x, tile.y, tile.w, tile.h, if i == self.selected { 4f32 } else { 2f32 }, border);
+            draw_tile_text(&lang.tr("level_select.level").replace("{n}", &(i + 1).to_string()), tile.x + 12f32, tile.y + 28f32, border, font);
+
+            if !unlocked {
+                draw_padlock(tile.point() + tile.size() * 0.5f32 + vec2(0f32, 10f32), LOCKED_COLOR);
+                continue;
+            }
+
+            draw_modifier_icons(&level.modifiers, vec2(tile.x + ICON_LEFT, tile.y + 12f32));
+
+            let thumbnail = Rect::new(tile.x + 12f32, tile.y + 40f32, tile.w - 24f32, THUMBNAIL_HEIGHT);
+            for block in level.blocks_in(thumbnail) {
+                block.draw();
+            }
+
+            // The best rating so far in the top right corner, with the stars still to earn dimmed
+            let best = progress.best(i);
+            for star in 0..3u8 {
+                let color = if star < best.stars { GOLD } else { LOCKED_COLOR };
+                let center = vec2(tile.right() - 16f32 - (2 - star) as f32 * TILE_STAR_RADIUS * 2.4f32, tile.y + 20f32);
+                draw_star(center, TILE_STAR_RADIUS, color);
+            }
+
+            let result = match (best.score, best.time) {
+                (Some(score), Some(time)) => lang
+                    .tr("level_select.best")
+                    .replace("{score}", &score.to_string())
+                    .replace("{time}", &format_time(time)),
+                _ => lang.tr("level_select.not_cleared").to_string(),
+            };
+            draw_tile_text(&result, tile.x + 12f32, tile.bottom() - 14f32, if best.score.is_some() { GREEN } else { GRAY }, font);
+        }
+
+        draw_centered_text(lang.tr("level_select.help"), screen_height() - 60f32, font);
+    }
+}
+
+// Tiles fill rows of GRID_COLUMNS, with the grid centered across the screen
+fn tile_rect(index: usize) -> Rect {
+    let grid_width = GRID_COLUMNS as f32 * TILE_SIZE.x + (GRID_COLUMNS - 1) as f32 * TILE_SPACING;
+    let left = (screen_width() - grid_width) * 0.5f32;
+    let (column, row) = (index % GRID_COLUMNS, index / GRID_COLUMNS);
+    Rect::new(
+        left + column as f32 * (TILE_SIZE.x + TILE_SPACING),
+        GRID_TOP + row as f32 * (TILE_SIZE.y + TILE_SPACING),
+        TILE_SIZE.x,
+        TILE_SIZE.y,
+    )
+}
+
+fn draw_tile_text(text: &str, x: f32, y: f32, color: Color, font: Font) {
+    draw_text_ex(
+        text,
+        x,
+        y,
+        TextParams {
+            font,
+            font_size: 22u16,
+            color,
+            ..Default::default()
+        },
+    );
+}
+
+// One small icon per modifier the level sets, left to right from `top_left`: a crossed-out paddle for no
+// aim, a moon for darkness, a clock for a time limit, a down arrow for gravity, and pips for a ball limit
+fn draw_modifier_icons(modifiers: &LevelModifiers, top_left: Vec2) {
+    let mut icons: Vec<fn(Rect)> = Vec::new();
+    if modifiers.no_paddle_aim {
+        icons.push(|area| {
+            draw_rectangle(area.x, area.bottom() - 5f32, area.w, 4f32, ICON_COLOR);
+            draw_line(area.x, area.y, area.right(), area.bottom() - 7f32, 2f32, ICON_COLOR);
+        });
+    }
+    if modifiers.darkness {
+        icons.push(|area| {
+            let center = area.point() + area.size() * 0.5f32;
+            draw_circle(center.x, center.y, area.w * 0.5f32, ICON_COLOR);
+            draw_circle(center.x + 4f32, center.y - 3f32, area.w * 0.4f32, BLACK);
+        });
+    }
+    if modifiers.timed.is_some() {
+        icons.push(|area| {
+            let center = area.point() + area.size() * 0.5f32;
+            draw_circle_lines(center.x, center.y, area.w * 0.5f32, 2f32, ICON_COLOR);
+            draw_line(center.x, center.y, center.x, center.y - 5f32, 2f32, ICON_COLOR);
+            draw_line(center.x, center.y, center.x + 4f32, center.y, 2f32, ICON_COLOR);
+        });
+    }
+    if modifiers.gravity.is_some() {
+        icons.push(|area| {
+            let center_x = area.x + area.w * 0.5f32;
+            draw_line(center_x, area.y, center_x, area.bottom() - 4f32, 2f32, ICON_COLOR);
+            draw_triangle(
+                vec2(area.x + 2f32, area.bottom() - 6f32),
+                vec2(area.right() - 2f32, area.bottom() - 6f32),
+                vec2(center_x, area.bottom()),
+                ICON_COLOR,
+            );
+        });
+    }
+    if modifiers.max_balls.is_some() {
+        icons.push(|area| {
+            draw_circle(area.x + 4f32, area.y + area.h * 0.5f32, 4f32, ICON_COLOR);
+            draw_circle_lines(area.right() - 4f32, area.y + area.h * 0.5f32, 4f32, 1.5f32, ICON_COLOR);
+        });
+    }
+    for (i, icon) in icons.iter().enumerate() {
+        icon(Rect::new(top_left.x + i as f32 * ICON_SPACING, top_left.y, ICON_SIZE, ICON_SIZE));
+    }
+}
+
+// A shackle over a body, centered on a point
+fn draw_padlock(center: Vec2, color: Color) {
+    draw_circle_lines(center.x, center.y - 14f32, 14f32, 5f32, color);
+    draw_rectangle(center.x - 22f32, center.y - 12f32, 44f32, 34f32, color);
+}
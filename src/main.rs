@@ -1,142 +1,1184 @@
 use macroquad::prelude::*;
 
+mod assets;
+mod background;
+mod camera_fx;
+mod classic_level;
+mod console;
+mod customize;
+mod editor;
+mod effects;
+mod frame_advance;
+mod heatmap;
+mod input;
+mod lang;
+mod leaderboard;
+mod level;
+mod level_select;
+mod interlude;
+mod menu;
+mod modifiers;
+mod mutators;
+mod music;
+mod progress;
+#[cfg(feature = "online")]
+mod online;
+mod session_log;
+mod settings;
+mod skins;
+mod sound;
+mod stats;
+mod tuning;
+
+use assets::{AssetLoader, Assets};
+use background::Theme;
+use camera_fx::CameraFx;
+use console::Console;
+use customize::{Customize, CustomizeAction};
+use editor::{Editor, EditorAction};
+use effects::EffectsPolicy;
+use input::{clipboard_get, clipboard_set, DoubleTap, InputBuffer};
+use lang::Lang;
+use leaderboard::{InitialsEntry, Leaderboard, LeaderboardEntry};
+use level::{decode_level, decode_level_in, encode_level, Level, LevelError, LevelModifiers};
+use level_select::{LevelSelect, LevelSelectAction};
+use menu::Menu;
+use modifiers::Modifiers;
+use frame_advance::FrameAdvance;
+use heatmap::Heatmap;
+use interlude::{Interlude, InterludeAction, ShopItem};
+use mutators::{MutatorsAction, MutatorsScreen};
+use music::MusicDirector;
+use progress::Progress;
+use session_log::SessionLog;
+use settings::{Difficulty, Settings};
+use skins::{Skin, MAX_TRAIL_LENGTH};
+use sound::{SoundEffect, SoundManager};
+use stats::{RunTotals, Stats};
+use tuning::{Physics, TuningPanel};
+
 const PADDLE_SIZE: Vec2 = const_vec2!([150f32, 40f32]);
-const PADDLE_SPEED: f32 = 700f32;
+// The auto-aim assist closes this fraction of the gap to its target per second, never moving faster
+// than this fraction of the paddle speed
+const AUTO_AIM_STRENGTH: f32 = 2f32;
+const AUTO_AIM_MAX_SPEED: f32 = 0.4f32;
+// Full block size and gap, scaled down together when the board wouldn't fit the window
 const BLOCK_SIZE: Vec2 = const_vec2!([100f32, 40f32]);
+const BLOCK_PADDING: f32 = 5f32;
+// Space kept clear between the board and the side walls
+const BOARD_MARGIN: f32 = 20f32;
+// Widest the playfield gets, as width over height, with the fixed aspect setting on
+const FIELD_ASPECT: f32 = 16f32 / 9f32;
 const BALL_SIZE: f32 = 50f32;
 const BALL_SPEED: f32 = 450f32;
+// Paddle bounce mapping: the ball's contact offset from the paddle center is normalized to -1..1,
+// bent by PADDLE_BOUNCE_CURVE (1 is linear, higher keeps the middle flatter and the edges steeper)
+// and scaled to PADDLE_MAX_BOUNCE_ANGLE, which is measured in radians from straight up
+const PADDLE_BOUNCE_CURVE: f32 = 1.8f32;
+const PADDLE_MAX_BOUNCE_ANGLE: f32 = 1.05f32;
+// A ball caught on the paddle's side by a slide goes back up this far from vertical, in radians, leaning
+// away from the paddle, so it can't be batted flat into the wall and down past it
+const PADDLE_SCOOP_ANGLE: f32 = 0.3f32;
+// With the tilting paddle setting on, Q and E tip the paddle's top up to PADDLE_MAX_TILT radians either
+// way, turning at PADDLE_TILT_SPEED radians per second
+const PADDLE_MAX_TILT: f32 = 0.3f32;
+const PADDLE_TILT_SPEED: f32 = 3f32;
+const LIFE_LOST_FLASH_TIME: f32 = 0.5f32;
+// Thickness of the red border drawn around the screen while the life-lost flash plays
+const LIFE_LOST_FLASH_WIDTH: f32 = 40f32;
+// Game time stays frozen this long after a life is lost
+const LIFE_LOST_HIT_STOP: f32 = 0.2f32;
+// How long after a serve the forgiveness floor stays up
+const FORGIVENESS_TIME: f32 = 2f32;
+// The served ball takes its color from the skin. Extra balls take the first pastel not already in play,
+// picked to stay clear of every block and portal color so a ball never blends into the board
+const SPAWNED_BALL_COLORS: [Color; 4] = [
+    Color::new(1f32, 0.95f32, 0.55f32, 1f32),
+    Color::new(0.7f32, 1f32, 0.85f32, 1f32),
+    Color::new(0.8f32, 0.75f32, 1f32, 1f32),
+    Color::new(0.65f32, 0.95f32, 1f32, 1f32),
+];
+// How long the paddle shows the color of the last ball it bounced
+const PADDLE_TINT_TIME: f32 = 0.4f32;
+// Drawn paddle size at the peak of a hit, easing back to 1 over PADDLE_SQUASH_TIME
+const PADDLE_SQUASH_SCALE: Vec2 = const_vec2!([1.15f32, 0.7f32]);
+const PADDLE_SQUASH_TIME: f32 = 0.15f32;
+// A dash covers DASH_DISTANCE pixels in DASH_TIME seconds, then can't be used again for DASH_COOLDOWN
+const DASH_DISTANCE: f32 = 200f32;
+const DASH_TIME: f32 = 0.1f32;
+const DASH_COOLDOWN: f32 = 2f32;
+// Outside classic movement, seconds the paddle takes to reach top speed from a standstill and to
+// coast to a stop once the keys are let go. Reversing gets both at once
+const PADDLE_ACCEL_TIME: f32 = 0.12f32;
+const PADDLE_STOP_TIME: f32 = 0.06f32;
+// Frames a tap on LEFT or RIGHT keeps pushing the paddle after it's let go, so a press and release
+// between two frames still moves it
+const PADDLE_TAP_FRAMES: u32 = 2;
+// Extra bounce angle in radians, in the dash's direction, given to a ball hit mid-dash
+const DASH_ENGLISH: f32 = 0.35f32;
+// Holding Ctrl spends focus to run every ball at FOCUS_SLOWDOWN of its speed. A full bar lasts
+// 1 / FOCUS_DRAIN seconds and refills at FOCUS_RECHARGE per second once Ctrl is let go
+const FOCUS_SLOWDOWN: f32 = 0.4f32;
+const FOCUS_DRAIN: f32 = 0.5f32;
+const FOCUS_RECHARGE: f32 = 0.1f32;
+// With one destructible block left, a ball heading for it from within LAST_BLOCK_RANGE eases down to
+// LAST_BLOCK_SLOWDOWN of its speed. It eases in and out over LAST_BLOCK_EASE and is cut off after
+// LAST_BLOCK_MAX_TIME in a level, so a ball circling the last block doesn't crawl forever
+const LAST_BLOCK_RANGE: f32 = 140f32;
+const LAST_BLOCK_SLOWDOWN: f32 = 0.3f32;
+const LAST_BLOCK_EASE: f32 = 0.15f32;
+const LAST_BLOCK_MAX_TIME: f32 = 1.5f32;
+// How much faster the whole game runs while Down fast-forwards a ball through the top half
+const FAST_FORWARD_SCALE: f32 = 1.75f32;
+// Paddle width bought in the shop for the next level, on top of any modifiers
+const ENLARGE_WIDTH: f32 = 1.4f32;
+const BIG_BALL_SIZE: f32 = 80f32;
+const BIG_BALL_TIME: f32 = 10f32;
+const FIREBALL_TIME: f32 = 6f32;
+// While the split effect lasts, block hits split the ball until the budget runs out
+const SPLIT_TIME: f32 = 8f32;
+const SPLITS_PER_POWER_UP: u32 = 3;
+// Angle in radians between a ball and the one that splits off it
+const SPLIT_ANGLE: f32 = 0.5f32;
+// No effect can put more balls than this in play
+const MAX_BALLS: usize = 8;
+// A ball that hasn't got further than this from one spot in STUCK_TIME seconds counts as stuck
+const STUCK_RADIUS: f32 = 40f32;
+const STUCK_TIME: f32 = 3f32;
+const POWER_UP_SIZE: Vec2 = const_vec2!([40f32, 20f32]);
+const POWER_UP_FALL_SPEED: f32 = 200f32;
+// Chance that a destroyed block drops a power-up
+const POWER_UP_DROP_CHANCE: f32 = 0.15f32;
+// How long the missing-font warning stays on screen
+const FONT_WARNING_TIME: f32 = 6f32;
+// How long the warning about a level that can't be played stays on screen
+const LEVEL_WARNING_TIME: f32 = 4f32;
+// The Memory mutator shows the board for MEMORY_PREVIEW_TIME, hides it over MEMORY_FADE_TIME, and then
+// only shows a block for MEMORY_REVEAL_TIME after it's hit
+const MEMORY_PREVIEW_TIME: f32 = 3f32;
+const MEMORY_FADE_TIME: f32 = 0.5f32;
+const MEMORY_REVEAL_TIME: f32 = 0.3f32;
+// The Fog mutator's lighting: how bright unlit areas stay, the fully lit radius around a ball and
+// half height of the band along the paddle's row, the distance light fades out over, and the size
+// of the cells the dimming is drawn in
+const FOG_BRIGHTNESS: f32 = 0.2f32;
+const FOG_LIGHT_RADIUS: f32 = 70f32;
+const FOG_ROW_HALF_HEIGHT: f32 = 40f32;
+const FOG_FALLOFF: f32 = 60f32;
+const FOG_CELL: f32 = 16f32;
+const PORTAL_COOLDOWN: f32 = 0.5f32;
+// After bouncing off the paddle a ball ignores it this long, so a ball the correction didn't
+// fully separate can't bounce twice
+const PADDLE_COOLDOWN: f32 = 0.1f32;
+// How far above the paddle's top the danger line sits when that mode is on
+const DANGER_LINE_OFFSET: f32 = 60f32;
+// Length of each dash, and of the gaps, on a side wall the ball wraps through
+const WRAP_DASH_LENGTH: f32 = 12f32;
+// Camera kick when a life is lost, in pixels, and zoom punch when an explosive block goes off
+const LIFE_LOST_SHAKE: f32 = 10f32;
+const EXPLOSION_PUNCH: f32 = 0.02f32;
+// Sparks thrown off a block that survives a hit, kept few so multiball stays cheap
+const SPARK_COUNT: usize = 3;
+const SPARK_LIFETIME: f32 = 0.25f32;
+const SPARK_SIZE: f32 = 4f32;
+// Points for destroying a block, and the points a trap block takes away instead
+const BLOCK_POINTS: i32 = 10;
+const TRAP_PENALTY: i32 = 30;
+// Debris thrown at the paddle when a trap breaks, which freezes the paddle if it connects
+const DEBRIS_SIZE: f32 = 16f32;
+const DEBRIS_SPEED: f32 = 350f32;
+const DEBRIS_STUN_TIME: f32 = 0.6f32;
+// How long the score shows red after points are lost
+const SCORE_FLASH_TIME: f32 = 0.5f32;
+// How quickly the HUD score closes the gap to the real one, as a fraction per second, and the slowest
+// it counts in points per second so the last few points don't crawl
+const SCORE_COUNT_RATE: f32 = 12f32;
+const SCORE_COUNT_MIN_SPEED: f32 = 60f32;
+// Blocks one ball destroys within this many seconds of each other count as a chain
+const CHAIN_WINDOW: f32 = 0.3f32;
+// Bonus for the second block in a chain, doubling with every block after it
+const CHAIN_BONUS: i32 = 5;
+// Chain lengths that get a word from the announcer in the middle of the screen, how long it stays
+// up, and how long after one before another can play
+const ANNOUNCEMENTS: [(u32, &str); 3] = [(5, "announce.nice"), (10, "announce.amazing"), (15, "announce.incredible")];
+const ANNOUNCEMENT_TIME: f32 = 0.8f32;
+const ANNOUNCEMENT_COOLDOWN: f32 = 2f32;
+// Times a campaign run can carry on from a game over, at half its score each time
+const MAX_CONTINUES: u32 = 2;
+// Lives at the start of a run, after a continue, and when a level is retried from a game over
+const STARTING_LIVES: i32 = 3;
+const POPUP_LIFETIME: f32 = 0.9f32;
+// The combo meter gains this much per block broken and loses this much per second, and block
+// points are multiplied by up to COMBO_MAX_MULTIPLIER as it fills
+const COMBO_FILL: f32 = 0.25f32;
+const COMBO_DRAIN: f32 = 0.3f32;
+const COMBO_MAX_MULTIPLIER: i32 = 4;
+const POPUP_RISE_SPEED: f32 = 60f32;
+// How long the win and game over headlines take to pop in
+const END_TEXT_ANIMATION_TIME: f32 = 0.4f32;
+// Star ratings pop in one after another once the headline has landed
+const STAR_POP_INTERVAL: f32 = 0.3f32;
+const STAR_RADIUS: f32 = 28f32;
+// How long the level progress bar flashes once the last block goes
+const PROGRESS_FLASH_TIME: f32 = 0.6f32;
+// How long R has to be held in campaign mode before the level restarts
+const RESTART_HOLD_TIME: f32 = 1f32;
+// How long Shift+R has to be held before the whole run restarts
+const RUN_RESTART_HOLD_TIME: f32 = 0.5f32;
 
+#[derive(Clone, Copy, PartialEq)]
 pub enum GameState {
+    Loading,
     Menu,
+    Settings,
+    Stats,
+    Leaderboard,
+    Editor,
+    LevelSelect,
+    Customize,
+    Mutators,
     Game,
+    Paused,
     Won,
+    // The shop between two campaign levels
+    Interlude,
     Dead,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameMode {
+    Campaign,
+    // Restarts are instant and never need confirming
+    Practice,
+}
+
+impl GameMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Campaign => "campaign",
+            GameMode::Practice => "practice",
+        }
+    }
+}
+
+// Where the blocks for a run come from
+enum BoardSource {
+    // A fresh random board each reset, the only kind that counts for the leaderboard
+    Generated,
+    // A layout being test played from the editor
+    Editor(Level),
+    // One of the hand-made levels, by its index in the campaign
+    Campaign { index: usize, level: Level },
+    // A board pasted in as a level code, decoded again on each reset so it fits the current field.
+    // Damaged blocks stay damaged
+    Shared(String),
+}
+
+// Things that happened during a frame of play, queued up and drained once the frame is simulated
+pub enum GameEvent {
+    BlockDestroyed { pos: Vec2, block_type: BlockType },
+    LifeLost { lives_left: i32 },
+    PowerUpCollected(PowerUpKind),
+    LevelCleared,
+    Chain { length: u32, bonus: i32 },
+    GameOver { score: i32 },
+    // The stuck ball watchdog moved a ball back to the middle, from where it had been wedged
+    BallUnstuck { pos: Vec2 },
+}
+
+impl GameEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameEvent::BlockDestroyed { .. } => "block_destroyed",
+            GameEvent::LifeLost { .. } => "life_lost",
+            GameEvent::PowerUpCollected(_) => "power_up_collected",
+            GameEvent::LevelCleared => "level_cleared",
+            GameEvent::Chain { .. } => "chain",
+            GameEvent::GameOver { .. } => "game_over",
+            GameEvent::BallUnstuck { .. } => "ball_unstuck",
+        }
+    }
+}
+
+// Entries on the main menu, top to bottom
+#[derive(Clone, Copy)]
+enum MenuEntry {
+    Campaign,
+    LevelSelect,
+    Practice,
+    Mutators,
+    Editor,
+    Customize,
+    Settings,
+    Stats,
+    Leaderboard,
+    Quit,
+}
+
+impl MenuEntry {
+    const ALL: [MenuEntry; 10] = [
+        MenuEntry::Campaign,
+        MenuEntry::LevelSelect,
+        MenuEntry::Practice,
+        MenuEntry::Mutators,
+        MenuEntry::Editor,
+        MenuEntry::Customize,
+        MenuEntry::Settings,
+        MenuEntry::Stats,
+        MenuEntry::Leaderboard,
+        MenuEntry::Quit,
+    ];
+
+    fn text_id(self) -> &'static str {
+        match self {
+            MenuEntry::Campaign => "menu.campaign",
+            MenuEntry::LevelSelect => "menu.level_select",
+            MenuEntry::Practice => "menu.practice",
+            MenuEntry::Mutators => "menu.mutators",
+            MenuEntry::Editor => "menu.editor",
+            MenuEntry::Customize => "menu.customize",
+            MenuEntry::Settings => "menu.settings",
+            MenuEntry::Stats => "menu.stats",
+            MenuEntry::Leaderboard => "menu.leaderboard",
+            MenuEntry::Quit => "menu.quit",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum PauseEntry {
+    Resume,
+    Quit,
+}
+
+impl PauseEntry {
+    fn text_id(self) -> &'static str {
+        match self {
+            PauseEntry::Resume => "pause.resume",
+            PauseEntry::Quit => "pause.quit",
+        }
+    }
+}
+
+// Yes/No prompts drawn over the current state, swallowing input until answered
+#[derive(Clone, Copy)]
+pub enum Prompt {
+    QuitToMenu,
+    ExitGame,
+}
+
 struct Paddle {
     rect: Rect,
+    tint: Color,
+    tint_timer: f32,
+    // Only changes how the paddle is drawn, the collision rect keeps its size
+    squash_timer: f32,
+    // The paddle can't move while this counts down
+    stun_timer: f32,
+    // Double tapping LEFT or RIGHT, or pressing Shift while moving, dashes that way
+    left_tap: DoubleTap,
+    right_tap: DoubleTap,
+    // Seconds left in the current dash and its direction, -1 for left
+    dash_timer: f32,
+    dash_direction: f32,
+    dash_cooldown: f32,
+    // Where the paddle was before this frame's move, for swept collision against fast balls
+    prev_x: f32,
+    // Pixels per second, negative to the left. Only used outside classic movement
+    vel: f32,
+    left_press: InputBuffer,
+    right_press: InputBuffer,
+    // Angle of the top surface in radians, positive turning it to face right. Always 0 without the
+    // tilting paddle setting
+    tilt: f32,
 }
 
 impl Paddle {
-    pub fn new() -> Self {
+    pub fn new(width: f32) -> Self {
+        Self::at(Rect::new(
+            screen_width() * 0.5f32 - width*0.5f32,
+            screen_height() - 100f32,
+            width,
+            PADDLE_SIZE.y,
+        ))
+    }
+
+    // A paddle resting at the given rect, not about to move
+    fn at(rect: Rect) -> Self {
         Self {
-            rect: Rect::new(
-                screen_width() * 0.5f32 - PADDLE_SIZE.x*0.5f32,
-                screen_height() - 100f32,
-                PADDLE_SIZE.x,
-                PADDLE_SIZE.y,
-            ),
+            rect,
+            tint: BLACK,
+            tint_timer: 0f32,
+            squash_timer: 0f32,
+            stun_timer: 0f32,
+            left_tap: DoubleTap::new(KeyCode::Left),
+            right_tap: DoubleTap::new(KeyCode::Right),
+            dash_timer: 0f32,
+            dash_direction: 0f32,
+            dash_cooldown: 0f32,
+            prev_x: rect.x,
+            vel: 0f32,
+            left_press: InputBuffer::with_frames(KeyCode::Left, PADDLE_TAP_FRAMES),
+            right_press: InputBuffer::with_frames(KeyCode::Right, PADDLE_TAP_FRAMES),
+            tilt: 0f32,
         }
     }
 
-    pub fn update(&mut self, dt: f32) {
-        let x_move = match (is_key_down(KeyCode::Left), is_key_down(KeyCode::Right)) {
+    // `assist_target` is the x the auto-aim assist would like the paddle's center at, if it's on
+    pub fn update(&mut self, dt: f32, settings: &Settings, assist_target: Option<f32>) {
+        self.prev_x = self.rect.x;
+        self.tint_timer = (self.tint_timer - dt).max(0f32);
+        self.squash_timer = (self.squash_timer - dt).max(0f32);
+        self.dash_cooldown = (self.dash_cooldown - dt).max(0f32);
+        // Taps are still read while stunned so a tap from before the stun can't pair with one after
+        let (left_double, right_double) = (self.left_tap.update(), self.right_tap.update());
+        self.left_press.update();
+        self.right_press.update();
+        let direction = if settings.invert_controls { -1f32 } else { 1f32 };
+
+        // Holding Q or E tips the paddle towards that side, swapped like movement with inverted controls.
+        // It settles back flat once both are let go, or while it's stunned
+        let tilt_target = match (is_key_down(KeyCode::Q), is_key_down(KeyCode::E)) {
+            _ if !settings.paddle_tilt || self.stun_timer > 0f32 => 0f32,
+            (true, false) => -PADDLE_MAX_TILT * direction,
+            (false, true) => PADDLE_MAX_TILT * direction,
+            _ => 0f32,
+        };
+        let tilt_step = PADDLE_TILT_SPEED * dt;
+        self.tilt += (tilt_target - self.tilt).clamp(-tilt_step, tilt_step);
+
+        if self.stun_timer > 0f32 {
+            self.stun_timer -= dt;
+            self.dash_timer = 0f32;
+            self.vel = 0f32;
+            return;
+        }
+
+        let (left, right) = if settings.classic_movement {
+            (is_key_down(KeyCode::Left), is_key_down(KeyCode::Right))
+        } else {
+            (
+                is_key_down(KeyCode::Left) || self.left_press.is_buffered(),
+                is_key_down(KeyCode::Right) || self.right_press.is_buffered(),
+            )
+        };
+        let x_move = match (left, right) {
+            (true, false) => -1f32,
+            (false, true) => 1f32,
+            _ => 0f32,
+        };
+
+        let shift_pressed = is_key_pressed(KeyCode::LeftShift) || is_key_pressed(KeyCode::RightShift);
+        let dash = match (left_double, right_double) {
             (true, false) => -1f32,
             (false, true) => 1f32,
+            _ if shift_pressed => x_move,
             _ => 0f32,
         };
+        if dash != 0f32 && self.dash_cooldown <= 0f32 {
+            self.dash_timer = DASH_TIME;
+            self.dash_direction = dash * direction;
+            self.dash_cooldown = DASH_COOLDOWN;
+        }
 
-        self.rect.x += x_move * dt * PADDLE_SPEED;
+        let field = Field::from_settings(settings);
+        // A dash replaces normal movement and isn't held to the normal speed
+        if self.dash_timer > 0f32 {
+            let step = dt.min(self.dash_timer);
+            self.rect.x += self.dash_direction * DASH_DISTANCE / DASH_TIME * step;
+            self.dash_timer -= step;
+            self.vel = 0f32;
+        } else if settings.classic_movement && x_move != 0f32 {
+            self.rect.x += x_move * direction * dt * settings.paddle_speed.pixels_per_second();
+        } else if !settings.classic_movement && (x_move != 0f32 || self.vel != 0f32) {
+            self.accelerate(x_move * direction, dt, settings.paddle_speed.pixels_per_second(), field.width() - self.rect.w);
+        } else if let Some(target) = assist_target {
+            // Eases part of the way over each frame, capped well under the normal speed so it only ever nudges
+            let gap = target - (self.rect.x + self.rect.w * 0.5f32);
+            let max_step = settings.paddle_speed.pixels_per_second() * AUTO_AIM_MAX_SPEED * dt;
+            self.rect.x += (gap * (AUTO_AIM_STRENGTH * dt).min(1f32)).clamp(-max_step, max_step);
+        }
 
         // If we hit the left wall
-        if self.rect.x < 0f32 {
-            self.rect.x = 0f32;
+        if self.rect.x < field.left {
+            self.rect.x = field.left;
+            self.vel = self.vel.max(0f32);
         }
 
         // If we hit the right wall
-        if self.rect.x > screen_width() - self.rect.w {
-            self.rect.x = screen_width() - self.rect.w;
+        if self.rect.x > field.right - self.rect.w {
+            self.rect.x = field.right - self.rect.w;
+            self.vel = self.vel.min(0f32);
         }
     }
 
-    pub fn draw(&self) {
-        draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, BLACK);
+    // Speeds up towards `heading` times the top speed, or coasts to a stop with no heading. The top
+    // speed is raised above the classic one by just enough that a run from wall to wall, `travel`
+    // pixels, takes as long as it does with classic movement
+    fn accelerate(&mut self, heading: f32, dt: f32, classic_speed: f32, travel: f32) {
+        let ramp_loss = classic_speed * PADDLE_ACCEL_TIME * 0.5f32;
+        let top_speed = classic_speed * travel / (travel - ramp_loss).max(ramp_loss);
+        let target = heading * top_speed;
+        let mut rate = if heading == 0f32 { top_speed / PADDLE_STOP_TIME } else { top_speed / PADDLE_ACCEL_TIME };
+        if self.vel * target < 0f32 {
+            rate += top_speed / PADDLE_STOP_TIME;
+        }
+        let step = rate * dt;
+        self.vel += (target - self.vel).clamp(-step, step);
+        self.rect.x += self.vel * dt;
+    }
+
+    // Flashes the paddle to the ball's color and squashes it. A hit during the animation
+    // restarts it rather than squashing further
+    pub fn hit(&mut self, color: Color) {
+        self.tint = color;
+        self.tint_timer = PADDLE_TINT_TIME;
+        self.squash_timer = PADDLE_SQUASH_TIME;
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        self.dash_timer > 0f32
+    }
+
+    pub fn stun(&mut self) {
+        self.stun_timer = DEBRIS_STUN_TIME;
+        self.hit(TRAP_COLOR);
+    }
+
+    pub fn draw(&self, skin: &Skin) {
+        let t = self.tint_timer / PADDLE_TINT_TIME;
+        let color = Color::from_vec(skin.paddle.to_vec().lerp(self.tint.to_vec(), t));
+
+        // Eases out of the squash, scaling around the bottom middle so the paddle stays on its row
+        let squash = (self.squash_timer / PADDLE_SQUASH_TIME).powi(2);
+        let size = self.rect.size() * Vec2::ONE.lerp(PADDLE_SQUASH_SCALE, squash);
+        let x = self.rect.x + (self.rect.w - size.x) * 0.5f32;
+        let y = self.rect.bottom() - size.y;
+        skin.draw_paddle(Rect::new(x, y, size.x, size.y), self.tilt, color);
+
+        // Refills under the paddle while the dash recharges
+        if self.dash_cooldown > 0f32 {
+            let charged = 1f32 - self.dash_cooldown / DASH_COOLDOWN;
+            let bar_y = self.rect.bottom() + 6f32;
+            draw_rectangle(self.rect.x, bar_y, self.rect.w, 4f32, Color::new(0f32, 0f32, 0f32, 0.5f32));
+            draw_rectangle(self.rect.x, bar_y, self.rect.w * charged, 4f32, SKYBLUE);
+        }
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum BlockType {
     Regular,
     SpawnBallOnDeath,
+    // Takes its neighbors with it when destroyed
+    Explosive,
+    // Bounces the ball like any block but never breaks, and isn't needed to clear the board
+    Indestructible,
+    // Indestructible, teleports the ball to the other portal sharing its pair id
+    Portal(u8),
+    // Costs points instead of scoring and throws debris at the paddle, but still has to be cleared
+    Trap,
+    // Breaks in one hit and shatters every brittle block it touches, which shatter theirs in turn
+    Brittle,
+}
+
+impl BlockType {
+    pub fn name(self) -> &'static str {
+        match self {
+            BlockType::Regular => "regular",
+            BlockType::SpawnBallOnDeath => "spawn_ball",
+            BlockType::Explosive => "explosive",
+            BlockType::Indestructible => "indestructible",
+            BlockType::Portal(_) => "portal",
+            BlockType::Trap => "trap",
+            BlockType::Brittle => "brittle",
+        }
+    }
+}
+
+// A ball striking a block, collected during the collision pass and applied afterwards
+#[derive(Clone, Copy)]
+struct BlockHit {
+    block: usize,
+    ball: usize,
+    contact: Vec2,
+    // Destroys the block outright rather than taking a single life, used by explosions
+    lethal: bool,
+}
+
+// Puts a frame's hits in reading order of the blocks they landed on, top row first and left to right,
+// then by ball, so scoring and popups always come out the same way for the same hits
+fn sort_hits(hits: &mut [BlockHit], blocks: &[Block]) {
+    hits.sort_by(|a, b| {
+        let (a_rect, b_rect) = (blocks[a.block].rect, blocks[b.block].rect);
+        a_rect
+            .y
+            .total_cmp(&b_rect.y)
+            .then(a_rect.x.total_cmp(&b_rect.x))
+            .then(a.ball.cmp(&b.ball))
+    });
 }
 
+// Frames a block shows white after surviving a hit
+const BLOCK_HIT_FLASH_FRAMES: u8 = 2;
+// Cracks grow with the square of the damage taken, one at half health and up to this many just
+// before the block breaks
+const MAX_CRACKS: f32 = 4f32;
+// How widely cracks fan out, in radians, around the direction from the first hit into the block
+const CRACK_SPREAD: f32 = 1.6f32;
+const CRACK_SEGMENTS: u32 = 3;
+
+// Accent colors shared by both halves of a portal pair, indexed by pair id
+const PORTAL_COLORS: [Color; 3] = [PURPLE, SKYBLUE, PINK];
+// Darker than the purple portal so the two can't be confused
+const TRAP_COLOR: Color = DARKPURPLE;
+// A deep, slightly see-through teal, like bottle glass, kept dark so it can't pass for an extra ball
+const BRITTLE_COLOR: Color = Color::new(0f32, 0.5f32, 0.55f32, 0.75f32);
+
+#[derive(Clone)]
 struct Block {
     rect: Rect,
     lives: i32,
+    max_lives: i32,
     block_type: BlockType,
+    hit_flash_frames: u8,
+    // Counts down from MEMORY_REVEAL_TIME after any hit, showing the block while the Memory mutator hides the board
+    reveal_timer: f32,
+    // Where the first damaging hit landed, relative to the block's corner. Cracks spread from here
+    crack_origin: Vec2,
+    // Column and row on the board's grid, for anything that goes by neighboring cells rather than
+    // distance, which changes with the level's padding
+    cell: (usize, usize),
 }
 
 impl Block {
-    pub fn new(pos: Vec2, block_type: BlockType) -> Self {
+    pub fn new(pos: Vec2, size: Vec2, block_type: BlockType) -> Self {
+        let lives = if block_type == BlockType::Brittle { 1 } else { 2 };
         Self {
-            rect: Rect::new(pos.x, pos.y, BLOCK_SIZE.x, BLOCK_SIZE.y),
-            lives: 2,
+            rect: Rect::new(pos.x, pos.y, size.x, size.y),
+            lives,
+            max_lives: lives,
             block_type,
+            hit_flash_frames: 0,
+            reveal_timer: 0f32,
+            crack_origin: size * 0.5f32,
+            cell: (0, 0),
         }
     }
 
-    pub fn draw(&self) {
-        let color = match self.block_type {
+    pub fn color(&self) -> Color {
+        match self.block_type {
             BlockType::Regular => match self.lives {
                 2 => RED,
                 _ => ORANGE,
             },
             BlockType::SpawnBallOnDeath => GREEN,
+            BlockType::Explosive => MAROON,
+            BlockType::Indestructible => LIGHTGRAY,
+            BlockType::Portal(pair) => PORTAL_COLORS[pair as usize % PORTAL_COLORS.len()],
+            BlockType::Trap => TRAP_COLOR,
+            BlockType::Brittle => BRITTLE_COLOR,
+        }
+    }
+
+    // Portals and indestructible blocks can't be broken, so they don't count towards clearing the board
+    pub fn is_destructible(&self) -> bool {
+        !matches!(self.block_type, BlockType::Portal(_) | BlockType::Indestructible)
+    }
+
+    pub fn draw(&self) {
+        self.draw_with(&EffectsPolicy::FULL, 1f32);
+    }
+
+    // With flashes turned off a hit outlines the block instead of filling it white
+    // `board_alpha` is how visible the whole board is, which only drops below one under the Memory
+    // mutator. A block that was just hit shows through it for a moment either way
+    pub fn draw_with(&self, effects: &EffectsPolicy, board_alpha: f32) {
+        let alpha = board_alpha.max(self.reveal_timer / MEMORY_REVEAL_TIME).min(1f32);
+        if alpha <= 0f32 {
+            return;
+        }
+        let faded = |color: Color| Color::new(color.r, color.g, color.b, color.a * alpha);
+        let flashing = self.hit_flash_frames > 0;
+        match self.block_type {
+            BlockType::Portal(_) => {
+                draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, faded(BLACK));
+                draw_rectangle_lines(self.rect.x, self.rect.y, self.rect.w, self.rect.h, 6f32, faded(self.color()));
+            },
+            BlockType::Indestructible => {
+                draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, faded(self.color()));
+                draw_rectangle_lines(self.rect.x, self.rect.y, self.rect.w, self.rect.h, 6f32, faded(GRAY));
+            },
+            _ if flashing && effects.flashes => draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, faded(WHITE)),
+            _ => {
+                draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, faded(self.color()));
+                self.draw_cracks(alpha);
+            },
+        }
+        if !(flashing && effects.flashes) {
+            self.draw_glyph(alpha);
+        }
+        if flashing && !effects.flashes {
+            draw_rectangle_lines(self.rect.x, self.rect.y, self.rect.w, self.rect.h, 3f32, faded(WHITE));
+        }
+    }
+
+    // A small mark in the middle of each special block saying what it does: a pair of balls for spawn-ball,
+    // a burst for explosive, a swirl for a portal, a cross for a trap, and a glint for brittle. Sized from the block so it stays
+    // legible at BLOCK_SIZE and still reads on the level select thumbnails
+    fn draw_glyph(&self, alpha: f32) {
+        let center = self.rect.point() + self.rect.size() * 0.5f32;
+        let size = self.rect.w.min(self.rect.h);
+        let thickness = (size * 0.08f32).max(1f32);
+        let faded = |color: Color| Color::new(color.r, color.g, color.b, color.a * alpha);
+        match self.block_type {
+            BlockType::SpawnBallOnDeath => {
+                let radius = size * 0.16f32;
+                draw_circle(center.x - radius * 1.2f32, center.y, radius, faded(WHITE));
+                draw_circle_lines(center.x + radius * 1.2f32, center.y, radius, thickness, faded(WHITE));
+            },
+            BlockType::Explosive => {
+                let core = vec2(self.rect.w * 0.3f32, self.rect.h * 0.4f32);
+                draw_rectangle(center.x - core.x * 0.5f32, center.y - core.y * 0.5f32, core.x, core.y, faded(ORANGE));
+                for i in 0..8 {
+                    let (sin, cos) = (i as f32 * std::f32::consts::FRAC_PI_4).sin_cos();
+                    let direction = vec2(cos, sin);
+                    let (start, end) = (center + direction * size * 0.25f32, center + direction * size * 0.4f32);
+                    draw_line(start.x, start.y, end.x, end.y, thickness, faded(YELLOW));
+                }
+            },
+            BlockType::Portal(_) => {
+                for ring in 1..=2 {
+                    draw_circle_lines(center.x, center.y, size * 0.14f32 * ring as f32, thickness, faded(self.color()));
+                }
+            },
+            BlockType::Trap => {
+                let reach = size * 0.22f32;
+                draw_line(center.x - reach, center.y - reach, center.x + reach, center.y + reach, thickness * 1.5f32, faded(BLACK));
+                draw_line(center.x - reach, center.y + reach, center.x + reach, center.y - reach, thickness * 1.5f32, faded(BLACK));
+            },
+            BlockType::Brittle => {
+                let reach = vec2(self.rect.w * 0.3f32, self.rect.h * 0.3f32);
+                let glint = faded(Color::new(1f32, 1f32, 1f32, 0.8f32));
+                draw_line(center.x - reach.x, center.y + reach.y, center.x + reach.x * 0.2f32, center.y - reach.y, thickness, glint);
+                draw_line(center.x, center.y + reach.y, center.x + reach.x, center.y - reach.y * 0.2f32, thickness, glint);
+            },
+            BlockType::Regular | BlockType::Indestructible => {},
+        }
+    }
+
+    // Jagged lines running from the first hit into the block, randomized from the block's position
+    // so the same block always cracks the same way and only ever gains lines as it takes more damage
+    fn draw_cracks(&self, alpha: f32) {
+        let damage = 1f32 - self.lives.max(0) as f32 / self.max_lives as f32;
+        let count = (damage * damage * MAX_CRACKS).round() as u32;
+        let mut state = ((self.rect.x.to_bits() as u64) << 32 | self.rect.y.to_bits() as u64) | 1;
+        let mut next = || {
+            // xorshift64, plenty for picking crack positions
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1u64 << 24) as f32
         };
 
-        draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, color);
+        let origin = self.rect.point() + self.crack_origin;
+        let inward = self.rect.point() + self.rect.size() * 0.5f32 - origin;
+        // A block that was never hit off center, like one caught in a blast, cracks every way
+        let (heading, spread) = if inward.length() < 1f32 {
+            (0f32, std::f32::consts::TAU)
+        } else {
+            (inward.y.atan2(inward.x), CRACK_SPREAD)
+        };
+
+        for _ in 0..count {
+            let mut start = origin;
+            let mut angle = heading + (next() - 0.5f32) * spread;
+            for _ in 0..CRACK_SEGMENTS {
+                angle += (next() - 0.5f32) * 0.8f32;
+                let length = 6f32 + next() * 10f32;
+                let end = (start + vec2(angle.cos(), angle.sin()) * length)
+                    .clamp(self.rect.point(), self.rect.point() + self.rect.size());
+                draw_line(start.x, start.y, end.x, end.y, 2f32, Color::new(0f32, 0f32, 0f32, 0.6f32 * alpha));
+                start = end;
+            }
+        }
     }
 }
 
+#[derive(Clone)]
 struct Ball {
     rect: Rect,
     vel: Vec2,
+    // Time before portals can grab the ball again, so it doesn't bounce straight back out of the exit
+    portal_cooldown: f32,
+    paddle_cooldown: f32,
+    color: Color,
+    // Burns through blocks instead of bouncing off them, still bouncing off walls and the paddle
+    fireball: bool,
+    // How many steps the last update was split into, shown on the debug overlay
+    substeps: u32,
+    // Blocks destroyed by this ball in its current chain, and the run time of the latest one
+    chain: u32,
+    last_destroy_time: f32,
+    // Recent centers, oldest first, for skins that draw a trail
+    trail: Vec<Vec2>,
+    // Multiplies BALL_SPEED, from the run's modifiers
+    speed_scale: f32,
+    // Top left corner before the last update, for swept collision against the paddle
+    prev_pos: Vec2,
+    // Where the ball was when it last got more than STUCK_RADIUS away, and how long ago that was
+    stuck_anchor: Vec2,
+    stuck_timer: f32,
 }
 
 impl Ball {
-    pub fn new(pos: Vec2) -> Self {
+    pub fn new(pos: Vec2, color: Color, speed_scale: f32) -> Self {
         Self {
             rect: Rect::new(pos.x, pos.y, BALL_SIZE, BALL_SIZE),
             // Randomizing vec values can mess up the length of the vector
             // We call normalize to ensure the length is always one
             vel: vec2(rand::gen_range(-1f32, 1f32), 1f32).normalize(),
+            portal_cooldown: 0f32,
+            paddle_cooldown: 0f32,
+            color,
+            fireball: false,
+            substeps: 1,
+            chain: 0,
+            last_destroy_time: f32::NEG_INFINITY,
+            trail: Vec::new(),
+            speed_scale,
+            prev_pos: pos,
+            stuck_anchor: pos,
+            stuck_timer: 0f32,
+        }
+    }
+
+    // With `wrap_sides` the ball passes through the side walls instead of bouncing off them
+    pub fn update(&mut self, dt: f32, field: Field, wrap_sides: bool) {
+        self.prev_pos = self.rect.point();
+        self.portal_cooldown = (self.portal_cooldown - dt).max(0f32);
+        self.paddle_cooldown = (self.paddle_cooldown - dt).max(0f32);
+        if self.trail.len() == MAX_TRAIL_LENGTH {
+            self.trail.remove(0);
+        }
+        self.trail.push(self.rect.point() + self.rect.size() * 0.5f32);
+
+        // Moves in steps of at most half a ball so fast balls or long frames can't carry it
+        // visibly past a wall before the bounce catches it
+        let speed = BALL_SPEED * self.speed_scale;
+        let distance = self.vel.length() * dt * speed;
+        self.substeps = ((distance / (BALL_SIZE * 0.5f32)).ceil() as u32).max(1);
+        let step = dt / self.substeps as f32;
+
+        for _ in 0..self.substeps {
+            self.rect.x += self.vel.x * step * speed;
+            self.rect.y += self.vel.y * step * speed;
+
+            if wrap_sides {
+                // Once fully out one side it comes in at the other with its velocity untouched. The
+                // previous position moves with it, so the paddle sweep doesn't see a jump across the field
+                let shift = if self.rect.x > field.right {
+                    -field.width() - self.rect.w
+                } else if self.rect.right() < field.left {
+                    field.width() + self.rect.w
+                } else {
+                    0f32
+                };
+                self.rect.x += shift;
+                self.prev_pos.x += shift;
+            } else {
+                // If we hit the left wall
+                // Only the sign is flipped so the angle set by the paddle survives the bounce
+                if self.rect.x < field.left {
+                    self.rect.x = field.left;
+                    self.vel.x = self.vel.x.abs();
+                }
+
+                // If we hit the right wall
+                if self.rect.x > field.right - self.rect.w {
+                    self.rect.x = field.right - self.rect.w;
+                    self.vel.x = -self.vel.x.abs();
+                }
+            }
+
+            // If we hit the ceiling
+            if self.rect.y < 0f32 {
+                self.rect.y = 0f32;
+                self.vel.y = self.vel.y.abs();
+            }
+        }
+    }
+
+    // How fast the ball is actually travelling, in pixels per second
+    pub fn speed(&self) -> f32 {
+        self.vel.length() * BALL_SPEED * self.speed_scale
+    }
+
+    // Resizes the ball around its center so growing or shrinking doesn't make it jump
+    pub fn set_size(&mut self, size: f32) {
+        let center = self.rect.point() + self.rect.size() * 0.5f32;
+        self.rect = Rect::new(center.x - size * 0.5f32, center.y - size * 0.5f32, size, size);
+    }
+
+    // A fireball always looks like one, whatever the skin, so the power-up stays readable
+    pub fn draw(&self, skin: &Skin) {
+        skin.draw_trail(&self.trail, self.rect.w);
+        if self.fireball {
+            draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, ORANGE);
+            let inset = self.rect.w * 0.2f32;
+            draw_rectangle(
+                self.rect.x + inset,
+                self.rect.y + inset,
+                self.rect.w - inset * 2f32,
+                self.rect.h - inset * 2f32,
+                YELLOW,
+            );
+        } else {
+            skin.draw_ball(self.rect, self.color);
+        }
+    }
+
+    // Small index number in the middle of the ball, shown by the debug overlay
+    pub fn draw_label(&self, index: usize, font: Font) {
+        let text = index.to_string();
+        let size = measure_text(&text, Some(font), 20, 1f32);
+        let center = self.rect.point() + self.rect.size() * 0.5f32;
+        draw_text_ex(
+            &text,
+            center.x - size.width * 0.5f32,
+            center.y + size.offset_y * 0.5f32,
+            TextParams {
+                font,
+                font_size: 20,
+                color: BLACK,
+                ..Default::default()
+            },
+        );
+    }
+}
+
+// A short-lived fleck of color thrown off by hits
+struct Particle {
+    pos: Vec2,
+    vel: Vec2,
+    life: f32,
+    color: Color,
+}
+
+impl Particle {
+    // Shoots a spark off in a random direction from the given point
+    pub fn spark(pos: Vec2, color: Color) -> Self {
+        let angle = rand::gen_range(0f32, std::f32::consts::TAU);
+        Self {
+            pos,
+            vel: vec2(angle.cos(), angle.sin()) * rand::gen_range(100f32, 250f32),
+            life: SPARK_LIFETIME,
+            color,
+        }
+    }
+
+    // Drifts slowly away from a burning ball to leave a trail behind it
+    pub fn ember(pos: Vec2) -> Self {
+        let color = if rand::gen_range(0f32, 1f32) < 0.5f32 { ORANGE } else { YELLOW };
+        Self {
+            pos: pos + vec2(rand::gen_range(-8f32, 8f32), rand::gen_range(-8f32, 8f32)),
+            vel: vec2(rand::gen_range(-20f32, 20f32), rand::gen_range(-20f32, 20f32)),
+            life: SPARK_LIFETIME,
+            color,
         }
     }
 
     pub fn update(&mut self, dt: f32) {
-        self.rect.x += self.vel.x * dt * BALL_SPEED;
-        self.rect.y += self.vel.y * dt * BALL_SPEED;
+        self.pos += self.vel * dt;
+        self.life -= dt;
+    }
 
-        // If we hit the left wall
-        if self.rect.x < 0f32 {
-            self.vel.x = 1f32;
+    pub fn draw(&self) {
+        let color = Color::new(self.color.r, self.color.g, self.color.b, self.life / SPARK_LIFETIME);
+        draw_rectangle(self.pos.x - SPARK_SIZE * 0.5f32, self.pos.y - SPARK_SIZE * 0.5f32, SPARK_SIZE, SPARK_SIZE, color);
+    }
+}
+
+// Text that floats up from where something happened and fades out. The text is a language id
+// with {n} replaced by the value, so popups follow the chosen language
+struct Popup {
+    pos: Vec2,
+    text_id: &'static str,
+    value: i32,
+    color: Color,
+    life: f32,
+}
+
+impl Popup {
+    pub fn new(pos: Vec2, text_id: &'static str, value: i32, color: Color) -> Self {
+        Self {
+            pos,
+            text_id,
+            value,
+            color,
+            life: POPUP_LIFETIME,
         }
+    }
 
-        // If we hit the right wall
-        if self.rect.x > screen_width() - self.rect.w {
-            self.vel.x = -1f32;
+    // A score change, white when points are gained and red when they're lost
+    pub fn points(pos: Vec2, delta: i32) -> Self {
+        if delta < 0 {
+            Self::new(pos, "popup.penalty", -delta, RED)
+        } else {
+            Self::new(pos, "popup.points", delta, WHITE)
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.pos.y -= POPUP_RISE_SPEED * dt;
+        self.life -= dt;
+    }
+
+    pub fn draw(&self, lang: &Lang, font: Font) {
+        let text = lang.tr(self.text_id).replace("{n}", &self.value.to_string());
+        let dims = measure_text(&text, Some(font), 26, 1f32);
+        draw_text_ex(
+            &text,
+            self.pos.x - dims.width * 0.5f32,
+            self.pos.y,
+            TextParams {
+                font,
+                font_size: 26,
+                color: Color::new(self.color.r, self.color.g, self.color.b, (self.life / POPUP_LIFETIME).min(1f32)),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+// Points for reaching the given chain length, doubling per block and held at 16 doublings so long
+// chains can't overflow the shift
+fn chain_bonus(length: u32) -> i32 {
+    CHAIN_BONUS << length.saturating_sub(2).min(16)
+}
+
+// Popup id for a chain of the given length
+fn chain_text_id(length: u32) -> &'static str {
+    match length {
+        2 => "popup.double",
+        3 => "popup.triple",
+        4 => "popup.quad",
+        _ => "popup.chain",
+    }
+}
+
+// Thrown from a broken trap block towards where the paddle was
+struct Debris {
+    rect: Rect,
+    vel: Vec2,
+}
+
+impl Debris {
+    pub fn new(center: Vec2, target: Vec2) -> Self {
+        Self {
+            rect: Rect::new(center.x - DEBRIS_SIZE * 0.5f32, center.y - DEBRIS_SIZE * 0.5f32, DEBRIS_SIZE, DEBRIS_SIZE),
+            vel: (target - center).normalize_or_zero() * DEBRIS_SPEED,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.rect = self.rect.offset(self.vel * dt);
+    }
+
+    pub fn draw(&self) {
+        draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, TRAP_COLOR);
+        draw_rectangle_lines(self.rect.x, self.rect.y, self.rect.w, self.rect.h, 2f32, RED);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PowerUpKind {
+    BigBall,
+    Fireball,
+    Split,
+}
+
+impl PowerUpKind {
+    const ALL: [PowerUpKind; 3] = [PowerUpKind::BigBall, PowerUpKind::Fireball, PowerUpKind::Split];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerUpKind::BigBall => "bigball",
+            PowerUpKind::Fireball => "fireball",
+            PowerUpKind::Split => "split",
         }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+
+    pub fn random() -> Self {
+        Self::ALL[rand::gen_range(0, Self::ALL.len())]
+    }
+}
+
+// A capsule dropped by a destroyed block that activates its effect when it touches the paddle
+struct PowerUp {
+    rect: Rect,
+    kind: PowerUpKind,
+}
 
-        // If we hit the ceiling
-        if self.rect.y < 0f32 {
-            self.vel.y = 1f32;
+impl PowerUp {
+    pub fn new(center: Vec2, kind: PowerUpKind) -> Self {
+        Self {
+            rect: Rect::new(
+                center.x - POWER_UP_SIZE.x * 0.5f32,
+                center.y - POWER_UP_SIZE.y * 0.5f32,
+                POWER_UP_SIZE.x,
+                POWER_UP_SIZE.y,
+            ),
+            kind,
         }
     }
 
+    pub fn update(&mut self, dt: f32) {
+        self.rect.y += dt * POWER_UP_FALL_SPEED;
+    }
+
     pub fn draw(&self) {
-        draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, WHITE);
+        let (color, label) = match self.kind {
+            PowerUpKind::BigBall => (SKYBLUE, "B"),
+            PowerUpKind::Fireball => (ORANGE, "F"),
+            PowerUpKind::Split => (GOLD, "S"),
+        };
+
+        draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, color);
+        draw_text(label, self.rect.x + self.rect.w * 0.5f32 - 5f32, self.rect.y + self.rect.h - 4f32, 20f32, BLACK);
     }
 }
 
+// Which face of the rect being hit the moving rect struck
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+// Everything known about a resolved collision, for the sounds, particles and bounce tweaks that hang off it
+#[derive(Clone, Copy, Debug)]
+pub struct Contact {
+    pub side: Side,
+    // The closest point on the hit rect to the moving rect's center, after correction
+    pub point: Vec2,
+    pub vel_before: Vec2,
+    pub vel_after: Vec2,
+}
+
 // AABB (axis-aligned bounding box) collision with positional correction
 // Essentially, AABB is a rectangular collision shape aligned to the base axes of the scene
 // which aligns to the x and y axis
-fn resolve_collision(a: &mut Rect, vel: &mut Vec2, b: &Rect) -> bool {
+fn resolve_contact(a: &mut Rect, vel: &mut Vec2, b: &Rect) -> Option<Contact> {
     // intersection returns an Option of the value that represents the area created by two overlapping rects
-    let intersection = match a.intersect(*b) { // Dereference as intersection takes an owned value of a Rect
-        Some(intersection) => intersection,
-        None => return false, //Early exit
-    };
+    let intersection = a.intersect(*b)?; // Dereference as intersection takes an owned value of a Rect
+    let vel_before = *vel;
     
     let a_center = a.point() + a.size() * 0.5f32;
     let b_center = b.point() + b.size() * 0.5f32;
@@ -146,214 +1188,3401 @@ fn resolve_collision(a: &mut Rect, vel: &mut Vec2, b: &Rect) -> bool {
     // This helps with collission direction as we can determine its horizontal direction
     let to_signum = to.signum();
 
-    match intersection.w > intersection.h {
+    // b lying below a means a came down onto b's top, and so on for the other sides
+    let side = match intersection.w > intersection.h {
         true => {
             //Bounce on the y axis
             a.y -= to_signum.y * intersection.h;
             vel.y = -to_signum.y * vel.y.abs();
+            if to_signum.y > 0f32 { Side::Top } else { Side::Bottom }
         },
         false => {
              // Bounce on the x axis
             a.x -= to_signum.x * intersection.w;
             vel.x = -to_signum.x * vel.x.abs();
+            if to_signum.x > 0f32 { Side::Left } else { Side::Right }
         }
-    }
-    true
+    };
+
+    let corrected_center = a.point() + a.size() * 0.5f32;
+    Some(Contact {
+        side,
+        point: corrected_center.clamp(b.point(), b.point() + b.size()),
+        vel_before,
+        vel_after: *vel,
+    })
 }
 
-// Resets the game after a player loses and wishes to play again
-fn reset_game(
-    score: &mut i32,
-    player_lives: &mut i32,
-    blocks: &mut Vec<Block>,
-    balls: &mut Vec<Ball>,
-    paddle: &mut Paddle,
-) {
-    *paddle = Paddle::new();
-    *score = 0;
-    *player_lives = 3;
-    balls.clear();
-    balls.push(Ball::new(vec2(screen_width() * 0.5f32 - BALL_SIZE * 0.5f32, screen_height() * 0.5f32,)));
-    blocks.clear();
-    init_blocks(blocks);
+// The middle of the paddle's top edge, the way along that edge to the right and the way out of it, with
+// the paddle turned `tilt` radians about its center the way it's drawn
+fn paddle_surface(paddle: &Rect, tilt: f32) -> (Vec2, Vec2, Vec2) {
+    let center = paddle.point() + paddle.size() * 0.5f32;
+    let normal = vec2(tilt.sin(), -tilt.cos());
+    (center + normal * paddle.h * 0.5f32, vec2(tilt.cos(), tilt.sin()), normal)
 }
 
-// Creates the board
-fn init_blocks(blocks: &mut Vec<Block>) {
-    let (width, height) = (6, 5);
-    let padding = 5f32;
-    let total_block_size = BLOCK_SIZE + vec2(padding, padding);
-    let board_start_pos = vec2((screen_width() - (total_block_size.x * width as f32)) * 0.5f32, 50f32);
+// Everything a paddle turned `tilt` radians covers, so a raised end still has the ball tested against it
+fn paddle_bounds(paddle: &Rect, tilt: f32) -> Rect {
+    let (sin, cos) = (tilt.sin().abs(), tilt.cos().abs());
+    let half = vec2(paddle.w * cos + paddle.h * sin, paddle.w * sin + paddle.h * cos) * 0.5f32;
+    let center = paddle.point() + paddle.size() * 0.5f32;
+    Rect::new(center.x - half.x, center.y - half.y, half.x * 2f32, half.y * 2f32)
+}
 
-    for i in 0..width * height {
-        let block_x = (i % width) as f32 * total_block_size.x;
-        let block_y = (i / width) as f32 * total_block_size.y;
+// Catches a falling ball that crossed the paddle's top this frame, even when a long frame carried it
+// clean through so they never overlapped. Both are moved in straight lines from where they were at the
+// start of the frame, and if the ball reaches the top surface, tilted or not, somewhere along its length
+// the ball is put back on it and bounced up
+fn swept_paddle_collision(ball: &mut Ball, paddle: &Paddle) -> Option<Contact> {
+    if ball.paddle_cooldown > 0f32 || ball.vel.y <= 0f32 {
+        return None;
+    }
+    let (top, along, normal) = paddle_surface(&paddle.rect, paddle.tilt);
+    let radius = ball.rect.h * 0.5f32;
+    let half_size = ball.rect.size() * 0.5f32;
+    let (start_center, end_center) = (ball.prev_pos + half_size, ball.rect.point() + half_size);
+    let start_top = top + vec2(paddle.prev_x - paddle.rect.x, 0f32);
+    // How far each center is above the surface, short of touching it
+    let (start_gap, end_gap) = ((start_center - start_top).dot(normal) - radius, (end_center - top).dot(normal) - radius);
+    if start_gap < 0f32 || end_gap >= 0f32 {
+        return None;
+    }
 
-        blocks.push(Block::new(board_start_pos + vec2(block_x, block_y), BlockType::Regular));
+    // How far through the frame the crossing happened
+    let t = start_gap / (start_gap - end_gap);
+    let center = start_center.lerp(end_center, t);
+    if (center - start_top.lerp(top, t)).dot(along).abs() >= paddle.rect.w * 0.5f32 + radius {
+        return None;
     }
 
-    for _ in 0..3 {
-        let rand_index = rand::gen_range(0, blocks.len());
-        blocks[rand_index].block_type = BlockType::SpawnBallOnDeath;
+    let vel_before = ball.vel;
+    ball.rect.x = center.x - radius;
+    ball.rect.y = center.y - radius;
+    ball.vel = reflect_off_paddle(ball.vel, paddle.tilt);
+    Some(Contact {
+        side: Side::Top,
+        point: center.clamp(paddle.rect.point(), paddle.rect.point() + paddle.rect.size()),
+        vel_before,
+        vel_after: ball.vel,
+    })
+}
+
+// The paddle's own take on resolve_contact. A ball whose center is still above the paddle's top edge is
+// bounced off the top however the rects overlap, since the plain AABB test would often push it out
+// sideways when the paddle slides into a ball falling beside it. Anything lower is a scoop off the
+// side: the ball is moved clear and sent steeply back up, leaning away from the paddle. The top is the
+// paddle's edge turned by `tilt`, which the ball is tested against as a circle and reflected off. A ball
+// already rising with its center below the paddle's middle, say after a scoop or the safety net, is left
+// to pass through rather than be knocked back down
+fn resolve_paddle_contact(ball: &mut Rect, vel: &mut Vec2, paddle: &Rect, tilt: f32) -> Option<Contact> {
+    ball.intersect(paddle_bounds(paddle, tilt))?;
+    let vel_before = *vel;
+    let ball_center = ball.point() + ball.size() * 0.5f32;
+    if vel.y < 0f32 && ball_center.y > paddle.y + paddle.h * 0.5f32 {
+        return None;
     }
+
+    let (top, along, normal) = paddle_surface(paddle, tilt);
+    let radius = ball.h * 0.5f32;
+    let height = (ball_center - top).dot(normal);
+    let side = if height > 0f32 {
+        // Over the lowered end of a tilted paddle the ball can be inside the bounds without touching yet
+        if height >= radius || (ball_center - top).dot(along).abs() >= paddle.w * 0.5f32 + radius {
+            return None;
+        }
+        let corrected = ball_center + normal * (radius - height);
+        ball.x = corrected.x - ball.w * 0.5f32;
+        ball.y = corrected.y - radius;
+        *vel = reflect_off_paddle(*vel, tilt);
+        Side::Top
+    } else {
+        ball.intersect(*paddle)?;
+        let outward = if ball_center.x < paddle.x + paddle.w * 0.5f32 { -1f32 } else { 1f32 };
+        ball.x = if outward < 0f32 { paddle.x - ball.w } else { paddle.right() };
+        *vel = vec2(outward * PADDLE_SCOOP_ANGLE.sin(), -PADDLE_SCOOP_ANGLE.cos()) * vel.length();
+        if outward < 0f32 { Side::Left } else { Side::Right }
+    };
+
+    let corrected_center = ball.point() + ball.size() * 0.5f32;
+    Some(Contact {
+        side,
+        point: corrected_center.clamp(paddle.point(), paddle.point() + paddle.size()),
+        vel_before,
+        vel_after: *vel,
+    })
 }
 
-fn draw_title_text(text: &str, font: Font) {
-    let dims = measure_text(text, Some(font), 50u16, 1.0f32);
-    draw_text_ex(
-        text,
-        screen_width() * 0.5f32 - dims.width * 0.5f32,
-        screen_height() * 0.5f32 - dims.height * 0.5f32,
-        TextParams {
-            font,
-            font_size: 50u16,
-            color: WHITE,
-            ..Default::default()
-        },
-    );
+// Mirrors a velocity in the paddle's top surface tipped `tilt` radians, so a tilted paddle sends balls
+// off towards the side it faces. A ball grazing a steep tilt could come out still heading down, so the
+// result is always turned upwards
+fn reflect_off_paddle(vel: Vec2, tilt: f32) -> Vec2 {
+    let normal = vec2(tilt.sin(), -tilt.cos());
+    let reflected = vel - 2f32 * vel.dot(normal) * normal;
+    vec2(reflected.x, -reflected.y.abs())
 }
 
-#[macroquad::main("Breakout")]
-async fn main() {
-    let font = load_ttf_font("res/OpenSans-Regular.ttf").await.unwrap();
-    let mut game_state = GameState::Menu;
-    let mut score = 0;
-    let mut player_lives = 3;
+// Something a ball can bounce off in the collision pass
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Surface {
+    Paddle,
+    Block(usize),
+}
 
-    let mut paddle = Paddle::new();
-    let mut blocks = Vec::new();
-    let mut balls = Vec::new();
+// Ball predictions step forward this much at a time and look at most this far ahead, in seconds
+const PREDICTION_STEP: f32 = 1f32 / 120f32;
+const PREDICTION_TIME: f32 = 3f32;
 
-    balls.push(Ball::new(vec2(screen_width() * 0.5f32, screen_height() * 0.6f32,)));
-    init_blocks(&mut blocks);
+// Picks the one surface a ball should bounce off this frame: whichever it overlaps the most.
+// Resolving in a fixed order instead lets a ball wedged between the paddle and a low block bounce
+// off whichever happened to come first. Portals never bounce, and neither do blocks for a fireball
+// The paddle is left out when it's None, for simulating a ball that hasn't reached it yet
+fn closest_surface(ball: &Ball, paddle: Option<&Rect>, blocks: &[Block]) -> Option<Surface> {
+    let overlap_area = |rect: &Rect| ball.rect.intersect(*rect).map(|overlap| overlap.w * overlap.h);
 
-    loop {
-        match game_state {
-            GameState::Menu => {
-                if is_key_pressed(KeyCode::Space) {
-                    game_state = GameState::Game;
+    let mut closest = None;
+    let mut closest_area = 0f32;
+    if let Some(paddle) = paddle.filter(|_| ball.paddle_cooldown <= 0f32) {
+        if let Some(area) = overlap_area(paddle) {
+            closest = Some(Surface::Paddle);
+            closest_area = area;
+        }
+    }
+    if !ball.fireball {
+        for (i, block) in blocks.iter().enumerate() {
+            if matches!(block.block_type, BlockType::Portal(_)) {
+                continue;
+            }
+            if let Some(area) = overlap_area(&block.rect) {
+                if closest.is_none() || area > closest_area {
+                    closest = Some(Surface::Block(i));
+                    closest_area = area;
                 }
-            },
-            GameState::Game => {
-                paddle.update(get_frame_time());
+            }
+        }
+    }
+    closest
+}
 
-                for ball in balls.iter_mut() {
-                    ball.update(get_frame_time());
-                }
+// Where and when a ball is expected to come down to a given height
+pub struct Prediction {
+    // The ball's center
+    pub x: f32,
+    // Seconds from now
+    pub time: f32,
+}
 
-                let mut spawn_later = vec![];
-                for ball in balls.iter_mut() {
-                    resolve_collision(&mut ball.rect, &mut ball.vel, &paddle.rect);
-                    for block in blocks.iter_mut() {
-                        // Checks if the ball collided with the paddle
-                        if resolve_collision(&mut ball.rect, &mut ball.vel, &block.rect) {
-                            block.lives -= 1;
-                            if block.lives <= 0 {
-                                score += 10;
-
-                                // Spawns a new ball if it is of the special block type
-                                if block.block_type == BlockType::SpawnBallOnDeath {
-                                    spawn_later.push(Ball::new(ball.rect.point()));
-                                }
-                            }
-                        }
+// Runs a copy of the ball forward with the same movement and bounce code as the real game,
+// assuming every block stays put, until its bottom edge reaches `y`. Gives up on a ball that
+// would enter a portal, or one that doesn't get there within PREDICTION_TIME. A level's `gravity`
+// bends the copy's heading each step the same way it does the real ball's
+fn predict_crossing(ball: &Ball, blocks: &[Block], y: f32, field: Field, wrap_sides: bool, gravity: Option<f32>) -> Option<Prediction> {
+    let pull = gravity.unwrap_or(0f32) * PREDICTION_STEP;
+    let mut ball = ball.clone();
+    let mut time = 0f32;
+    while time < PREDICTION_TIME {
+        if ball.rect.bottom() >= y {
+            return Some(Prediction {
+                x: ball.rect.x + ball.rect.w * 0.5f32,
+                time,
+            });
+        }
+
+        if pull > 0f32 {
+            ball.vel = (ball.vel + vec2(0f32, pull)).normalize();
+        }
+        ball.update(PREDICTION_STEP, field, wrap_sides);
+        time += PREDICTION_STEP;
+        let entering_portal = blocks.iter().any(|block| {
+            matches!(block.block_type, BlockType::Portal(_)) && ball.portal_cooldown <= 0f32 && ball.rect.overlaps(&block.rect)
+        });
+        if entering_portal {
+            return None;
+        }
+        if let Some(Surface::Block(i)) = closest_surface(&ball, None, blocks) {
+            resolve_contact(&mut ball.rect, &mut ball.vel, &blocks[i].rect);
+        }
+    }
+    None
+}
+
+// Moves the ball out of the exit portal on the side it's heading, keeping its velocity
+fn teleport_ball(ball: &mut Ball, exit: &Rect) {
+    let exit_center = exit.point() + exit.size() * 0.5f32;
+    let offset = if ball.vel.y.abs() >= ball.vel.x.abs() {
+        vec2(0f32, ball.vel.y.signum() * (exit.h + ball.rect.h) * 0.5f32)
+    } else {
+        vec2(ball.vel.x.signum() * (exit.w + ball.rect.w) * 0.5f32, 0f32)
+    };
+    let ball_center = exit_center + offset;
+
+    ball.rect.x = ball_center.x - ball.rect.w * 0.5f32;
+    ball.rect.y = ball_center.y - ball.rect.h * 0.5f32;
+    ball.portal_cooldown = PORTAL_COOLDOWN;
+}
+
+// Treats the paddle's top surface as slightly curved, like a real Breakout paddle, so the further
+// from the center the ball lands the more steeply outward it deflects. Returns the bounce angle
+// in radians from straight up, negative to the left. The curve and steepest angle come from the tuned physics
+fn paddle_bounce_angle(ball: &Rect, paddle: &Rect, physics: &Physics) -> f32 {
+    let ball_center = ball.x + ball.w * 0.5f32;
+    let paddle_center = paddle.x + paddle.w * 0.5f32;
+    let offset = ((ball_center - paddle_center) / (paddle.w * 0.5f32 + ball.w * 0.5f32)).clamp(-1f32, 1f32);
+
+    offset.signum() * offset.abs().powf(physics.bounce_curve) * physics.max_bounce_angle
+}
+
+// Everything tied to the current run, so it can be reset or snapshotted as one unit
+struct Game {
+    mode: GameMode,
+    score: i32,
+    player_lives: i32,
+    paddle: Paddle,
+    // How the paddle and ball are drawn, picked on the customize screen
+    skin: &'static Skin,
+    // Which screen effects are allowed, refreshed from the settings every frame
+    effects: EffectsPolicy,
+    // Where the side walls are, refreshed every frame
+    field: Field,
+    // Assist adjustments and mutators, picked up from the settings when the run starts, and what
+    // actually applies on the current level once its own modifiers are laid over them
+    run_modifiers: Modifiers,
+    modifiers: Modifiers,
+    level_rules: LevelModifiers,
+    blocks: Vec<Block>,
+    balls: Vec<Ball>,
+    power_ups: Vec<PowerUp>,
+    debris: Vec<Debris>,
+    particles: Vec<Particle>,
+    popups: Vec<Popup>,
+    // Seconds left on the big ball effect
+    big_ball_timer: f32,
+    // Seconds left on the fireball effect
+    fireball_timer: f32,
+    // Seconds left on the split effect, and how many more splits it can cause
+    split_timer: f32,
+    splits_left: u32,
+    level_start: LevelSnapshot,
+    // How long R has been held towards a level restart
+    restart_hold: f32,
+    // How long Shift+R has been held towards a run restart
+    run_restart_hold: f32,
+    // Counts down from LIFE_LOST_FLASH_TIME after a life is lost, drawing a fading red overlay
+    life_lost_flash: f32,
+    // While above zero game time is frozen, counting down in real time
+    hit_stop: f32,
+    // The ball sits on the paddle until Space serves it
+    awaiting_serve: bool,
+    // Seconds left on the forgiveness floor, which bounces back the first ball that would be lost
+    forgiveness_timer: f32,
+    // Whether this run has the Easy safety net, and whether it's still waiting to save a ball this level
+    safety_net: bool,
+    safety_net_armed: bool,
+    // Counts down after points are lost, showing the score in red
+    score_flash: f32,
+    // What the HUD shows, counting towards `score` rather than jumping to it
+    displayed_score: f32,
+    // Combo meter from 0 to 1, emptied whenever a ball touches the paddle
+    combo: f32,
+    // Blocks destroyed this level under the Memory mutator, left as faint outlines
+    destroyed_outlines: Vec<Rect>,
+    // Focus bar from 0 to 1, refilled at the start of every level, and whether it's being spent
+    focus: f32,
+    focusing: bool,
+    // How far into the last block slow motion, from 0 to 1, and how long it has run this level
+    slow_motion: f32,
+    slow_motion_time: f32,
+    // Whether Down is fast-forwarding the game right now, and whether a collision cut it off, after
+    // which it waits for Down to be let go
+    fast_forward: bool,
+    fast_forward_spent: bool,
+    // Seed the board was generated from, so a run can be reproduced
+    seed: u64,
+    // Set on the end screen to make the next reset_game use this seed rather than a fresh one
+    replay_seed: Option<u64>,
+    // Where the balls went this run, only kept with --heatmap
+    heatmap: Option<Box<Heatmap>>,
+    board: BoardSource,
+    // Set once any console cheat is used, making the run ineligible for high scores
+    cheated: bool,
+    // Ball and paddle values changed from the debug overlay. Kept across runs, and any run started or
+    // played with them away from the defaults counts as cheated
+    physics: Physics,
+    // Game overs this run has been continued from
+    continues_used: u32,
+    // Times the stuck ball watchdog has had to step in this run
+    balls_unstuck: u32,
+    // Seconds spent in play this run, not counting pauses or menus
+    elapsed: f32,
+    // Blocks broken this run, counted from BlockDestroyed events
+    blocks_destroyed: u32,
+    // What the lifetime stats were given at this run's last game over, if it's had one
+    stats_recorded: Option<RunTotals>,
+    // Most blocks one ball destroyed in a single chain this run
+    longest_chain: u32,
+    // Seconds spent and lives lost on the current level, reset whenever it starts or restarts
+    level_time: f32,
+    level_lives_lost: u32,
+    // Rating for the campaign level just cleared, shown on the win screen
+    stars_earned: Option<u8>,
+    events: Vec<GameEvent>,
+    // Destructible blocks this level started with plus any added since, and how many have been
+    // destroyed, kept up to date from events rather than by scanning the board
+    level_blocks_total: u32,
+    level_blocks_destroyed: u32,
+    // What the progress bar shows, which only moves forward even if blocks are added mid-level
+    progress_shown: f32,
+    progress_flash: f32,
+    // Bought between campaign levels, lasting until the next one starts
+    enlarged: bool,
+    shielded: bool,
+}
+
+// The state of the run when the current level was entered, restored by a level restart
+struct LevelSnapshot {
+    score: i32,
+    player_lives: i32,
+    blocks: Vec<Block>,
+}
+
+impl Game {
+    pub fn new(settings: &Settings) -> Self {
+        let mut game = Self {
+            mode: GameMode::Campaign,
+            score: 0,
+            player_lives: 3,
+            paddle: Paddle::new(PADDLE_SIZE.x),
+            skin: skins::find(&settings.skin),
+            effects: EffectsPolicy::from_settings(settings),
+            field: Field::from_settings(settings),
+            run_modifiers: Modifiers::NONE,
+            modifiers: Modifiers::NONE,
+            level_rules: LevelModifiers::default(),
+            blocks: Vec::new(),
+            balls: Vec::new(),
+            power_ups: Vec::new(),
+            debris: Vec::new(),
+            particles: Vec::new(),
+            popups: Vec::new(),
+            big_ball_timer: 0f32,
+            fireball_timer: 0f32,
+            split_timer: 0f32,
+            splits_left: 0,
+            level_start: LevelSnapshot {
+                score: 0,
+                player_lives: 3,
+                blocks: Vec::new(),
+            },
+            restart_hold: 0f32,
+            run_restart_hold: 0f32,
+            life_lost_flash: 0f32,
+            hit_stop: 0f32,
+            awaiting_serve: false,
+            forgiveness_timer: 0f32,
+            safety_net: false,
+            safety_net_armed: false,
+            score_flash: 0f32,
+            displayed_score: 0f32,
+            combo: 0f32,
+            destroyed_outlines: Vec::new(),
+            focus: 1f32,
+            focusing: false,
+            slow_motion: 0f32,
+            slow_motion_time: 0f32,
+            fast_forward: false,
+            fast_forward_spent: false,
+            seed: 0,
+            replay_seed: None,
+            heatmap: None,
+            board: BoardSource::Generated,
+            cheated: false,
+            physics: Physics::DEFAULT,
+            continues_used: 0,
+            balls_unstuck: 0,
+            elapsed: 0f32,
+            blocks_destroyed: 0,
+            stats_recorded: None,
+            level_time: 0f32,
+            level_lives_lost: 0,
+            stars_earned: None,
+            longest_chain: 0,
+            events: Vec::new(),
+            level_blocks_total: 0,
+            level_blocks_destroyed: 0,
+            progress_shown: 0f32,
+            progress_flash: 0f32,
+            enlarged: false,
+            shielded: false,
+        };
+        reset_game(&mut game, settings);
+        game
+    }
+
+    // Remembers the score, lives, and board so the level can be restarted from this point
+    pub fn snapshot_level(&mut self) {
+        self.level_start = LevelSnapshot {
+            score: self.score,
+            player_lives: self.player_lives,
+            blocks: self.blocks.clone(),
+        };
+        self.safety_net_armed = self.safety_net || self.shielded;
+        self.reset_level_progress();
+    }
+
+    // Counts the board once as a level starts, after which events keep the totals current
+    fn reset_level_progress(&mut self) {
+        self.level_time = 0f32;
+        self.level_lives_lost = 0;
+        self.stars_earned = None;
+        self.level_blocks_total = self.blocks.iter().filter(|block| block.is_destructible()).count() as u32;
+        self.level_blocks_destroyed = 0;
+        self.progress_shown = 0f32;
+        self.progress_flash = 0f32;
+        self.combo = 0f32;
+        self.focus = 1f32;
+        self.focusing = false;
+        self.slow_motion = 0f32;
+        self.slow_motion_time = 0f32;
+        self.fast_forward = false;
+        self.destroyed_outlines.clear();
+    }
+
+    // How visible the board is. Under the Memory mutator it's shown in full for MEMORY_PREVIEW_TIME
+    // at the start of each level, then fades out over MEMORY_FADE_TIME
+    pub fn board_alpha(&self) -> f32 {
+        if !self.modifiers.memory {
+            return 1f32;
+        }
+        1f32 - ((self.level_time - MEMORY_PREVIEW_TIME) / MEMORY_FADE_TIME).clamp(0f32, 1f32)
+    }
+
+    // Spends focus while it's held and there's some left, otherwise refills it. Nothing happens while
+    // the ball waits to be served, since there's nothing to slow down
+    pub fn update_focus(&mut self, held: bool, dt: f32) {
+        self.focusing = held && self.focus > 0f32 && !self.awaiting_serve;
+        if self.focusing {
+            self.focus = (self.focus - FOCUS_DRAIN * dt).max(0f32);
+        } else if !held {
+            self.focus = (self.focus + FOCUS_RECHARGE * dt).min(1f32);
+        }
+    }
+
+    // Whether a ball is closing in on the only destructible block left
+    fn approaching_last_block(&self) -> bool {
+        let mut destructible = self.blocks.iter().filter(|block| block.is_destructible());
+        let (Some(last), None) = (destructible.next(), destructible.next()) else {
+            return false;
+        };
+        let target = last.rect.point() + last.rect.size() * 0.5f32;
+        self.balls.iter().any(|ball| {
+            let to_block = target - (ball.rect.point() + ball.rect.size() * 0.5f32);
+            to_block.length() < LAST_BLOCK_RANGE && to_block.dot(ball.vel) > 0f32
+        })
+    }
+
+    // Timed in game time rather than ball time, so the slow motion itself doesn't drag out how long it lasts
+    pub fn update_slow_motion(&mut self, enabled: bool, dt: f32) {
+        let active = enabled && self.slow_motion_time < LAST_BLOCK_MAX_TIME && !self.awaiting_serve && self.approaching_last_block();
+        if active {
+            self.slow_motion_time += dt;
+            self.slow_motion = (self.slow_motion + dt / LAST_BLOCK_EASE).min(1f32);
+        } else {
+            self.slow_motion = (self.slow_motion - dt / LAST_BLOCK_EASE).max(0f32);
+        }
+    }
+
+    // Runs the game at FAST_FORWARD_SCALE while Down is held through a long quiet stretch, which is
+    // every ball up in the top half with nothing to hit. It drops back the moment a ball comes below
+    // the middle, so it can't carry one past the paddle
+    pub fn update_fast_forward(&mut self, held: bool) {
+        if !held {
+            self.fast_forward_spent = false;
+        }
+        let high = self.balls.iter().all(|ball| ball.rect.bottom() < screen_height() * 0.5f32);
+        self.fast_forward = held && !self.fast_forward_spent && high && !self.awaiting_serve && !self.balls.is_empty();
+    }
+
+    // A ball hit something, so normal speed comes back until Down is pressed again
+    pub fn cut_fast_forward(&mut self) {
+        if self.fast_forward {
+            self.fast_forward = false;
+            self.fast_forward_spent = true;
+        }
+    }
+
+    // What BALL_SPEED is multiplied by right now, from the run's modifiers, tuning, focus and the last block
+    pub fn ball_speed_scale(&self) -> f32 {
+        let focus = if self.focusing { FOCUS_SLOWDOWN } else { 1f32 };
+        let slow_motion = 1f32 + (LAST_BLOCK_SLOWDOWN - 1f32) * self.slow_motion;
+        self.modifiers.ball_speed_at(self.level_time) * self.physics.ball_speed * focus * slow_motion
+    }
+
+    // Eases the bar towards the real progress, never letting it slide back
+    pub fn update_level_progress(&mut self, dt: f32) {
+        let target = self.level_progress();
+        if target > self.progress_shown {
+            self.progress_shown = (self.progress_shown + dt * 2f32).min(target);
+        }
+        self.progress_flash = (self.progress_flash - dt).max(0f32);
+    }
+
+    // Fraction of the level's destructible blocks destroyed so far
+    pub fn level_progress(&self) -> f32 {
+        if self.level_blocks_total == 0 {
+            return 1f32;
+        }
+        (self.level_blocks_destroyed as f32 / self.level_blocks_total as f32).min(1f32)
+    }
+
+    pub fn can_continue(&self) -> bool {
+        self.mode == GameMode::Campaign && self.continues_used < MAX_CONTINUES
+    }
+
+    // Picks up from the moment of the game over, with the board and its damage left exactly as they
+    // were and the last ball already waiting on the paddle. The level's starting score is halved too,
+    // so restarting the level afterwards can't win the points back
+    // A timed level's clock starts again, since it would otherwise end the run straight away
+    pub fn continue_run(&mut self) {
+        if self.level_rules.timed.is_some() {
+            self.level_time = 0f32;
+        }
+        self.continues_used += 1;
+        self.player_lives = STARTING_LIVES;
+        self.score /= 2;
+        self.level_start.score /= 2;
+    }
+
+    // The score with the mutators' multiplier applied, which is what a finished run is worth
+    pub fn final_score(&self) -> i32 {
+        (self.score as f32 * self.modifiers.score_multiplier).round() as i32
+    }
+
+    // Steps up evenly as the meter fills, reaching COMBO_MAX_MULTIPLIER only once it's full
+    pub fn combo_multiplier(&self) -> i32 {
+        1 + (self.combo * (COMBO_MAX_MULTIPLIER - 1) as f32) as i32
+    }
+
+    // Puts the board, score, and lives back to how they were when the level started
+    pub fn restart_level(&mut self) {
+        self.paddle = self.new_paddle();
+        self.score = self.level_start.score;
+        self.player_lives = self.level_start.player_lives;
+        self.blocks = self.level_start.blocks.clone();
+        self.safety_net_armed = self.safety_net || self.shielded;
+        self.reset_level_progress();
+        self.restart_hold = 0f32;
+        self.run_restart_hold = 0f32;
+        self.life_lost_flash = 0f32;
+        self.hit_stop = 0f32;
+        self.forgiveness_timer = 0f32;
+        self.particles.clear();
+        self.popups.clear();
+        self.debris.clear();
+        self.score_flash = 0f32;
+        // A restart isn't points being won or lost, so the HUD shows the new score straight away
+        self.displayed_score = self.score as f32;
+        self.clear_power_ups();
+        self.serve_ball();
+    }
+
+    // Plays the level again from a game over with full lives rather than the ones it was started
+    // with. The score goes back to what it was as the level started, so the blocks broken before
+    // dying don't count twice
+    pub fn retry_level(&mut self) {
+        self.restart_level();
+        self.player_lives = STARTING_LIVES;
+    }
+
+    // Slides everything in play across when the walls move, so with the fixed aspect setting on a
+    // resized window keeps the board centered between them at the same size
+    pub fn follow_field(&mut self, field: Field) {
+        let shift = field.center() - self.field.center();
+        self.field = field;
+        if shift == 0f32 {
+            return;
+        }
+        for block in self.blocks.iter_mut().chain(self.level_start.blocks.iter_mut()) {
+            block.rect.x += shift;
+        }
+        for ball in self.balls.iter_mut() {
+            ball.rect.x += shift;
+            ball.trail.clear();
+        }
+        for power_up in self.power_ups.iter_mut() {
+            power_up.rect.x += shift;
+        }
+        self.paddle.rect.x += shift;
+    }
+
+    // Where the lowest falling ball is expected to reach the paddle, for the auto-aim assist
+    pub fn auto_aim_target(&self) -> Option<f32> {
+        let ball = self
+            .balls
+            .iter()
+            .filter(|ball| ball.vel.y > 0f32)
+            .max_by(|a, b| a.rect.y.total_cmp(&b.rect.y))?;
+        predict_crossing(ball, &self.blocks, self.paddle.rect.y, self.field, self.modifiers.wrap_sides, self.level_rules.gravity).map(|landing| landing.x)
+    }
+
+    pub fn campaign_index(&self) -> Option<usize> {
+        match self.board {
+            BoardSource::Campaign { index, .. } => Some(index),
+            _ => None,
+        }
+    }
+
+    // The campaign level after this one, if this is a campaign run with levels still to go
+    pub fn next_campaign_index(&self, campaign_len: usize) -> Option<usize> {
+        match self.board {
+            BoardSource::Campaign { index, .. } if self.mode == GameMode::Campaign => Some(index + 1).filter(|&next| next < campaign_len),
+            _ => None,
+        }
+    }
+
+    // Carries the run on into another campaign level with the same score and lives, less whatever
+    // was bought in the shop. The level snapshot is taken after the purchase, so a restart keeps it
+    pub fn start_next_level(&mut self, index: usize, level: Level, item: Option<ShopItem>) {
+        self.enlarged = item == Some(ShopItem::Enlarge);
+        self.shielded = item == Some(ShopItem::Shield);
+        if let Some(item) = item {
+            self.score -= item.cost();
+            if item == ShopItem::ExtraLife {
+                self.player_lives += 1;
+            }
+        }
+        self.blocks = level.blocks_in_field(self.field);
+        self.board = BoardSource::Campaign { index, level };
+        self.apply_level_rules();
+        self.snapshot_level();
+        self.restart_level();
+    }
+
+    // Picks up the current board's own modifiers. A level with any sets aside the mutators picked
+    // for the run, though the run's score multiplier stays as it was
+    fn apply_level_rules(&mut self) {
+        self.level_rules = match &self.board {
+            BoardSource::Editor(level) | BoardSource::Campaign { level, .. } => level.modifiers,
+            BoardSource::Generated | BoardSource::Shared(_) => LevelModifiers::default(),
+        };
+        self.modifiers = if self.level_rules.is_empty() {
+            self.run_modifiers.clone()
+        } else {
+            self.run_modifiers.without_mutators()
+        };
+        self.modifiers.fog |= self.level_rules.darkness;
+    }
+
+    // A safety net for bugs that wedge a ball somewhere: a ball that stays within STUCK_RADIUS of one spot
+    // for STUCK_TIME is sent off from where it is, straight at the middle of the field. Each rescue is
+    // counted on the debug overlay and queued as an event so the session log shows where it happened
+    pub fn watch_for_stuck_balls(&mut self, dt: f32) {
+        let center = vec2(self.field.center(), screen_height() * 0.5f32);
+        for ball in self.balls.iter_mut() {
+            if ball.rect.point().distance(ball.stuck_anchor) > STUCK_RADIUS {
+                ball.stuck_anchor = ball.rect.point();
+                ball.stuck_timer = 0f32;
+                continue;
+            }
+            ball.stuck_timer += dt;
+            if ball.stuck_timer >= STUCK_TIME {
+                self.events.push(GameEvent::BallUnstuck { pos: ball.rect.point() });
+                self.balls_unstuck += 1;
+                // A ball already sitting in the middle just drops, like a serve
+                let ball_center = ball.rect.point() + ball.rect.size() * 0.5f32;
+                let fallback = vec2(rand::gen_range(-1f32, 1f32), 1f32).normalize();
+                ball.vel = (center - ball_center).try_normalize().unwrap_or(fallback);
+                ball.stuck_anchor = ball.rect.point();
+                ball.stuck_timer = 0f32;
+            }
+        }
+    }
+
+    // The gap the current board was laid out with
+    fn board_padding(&self) -> f32 {
+        match &self.board {
+            BoardSource::Editor(level) | BoardSource::Campaign { level, .. } => level.padding,
+            BoardSource::Generated | BoardSource::Shared(_) => BLOCK_PADDING,
+        }
+    }
+
+    // Most balls allowed in play at once on this level
+    fn max_balls(&self) -> usize {
+        self.level_rules.max_balls.unwrap_or(MAX_BALLS)
+    }
+
+    // Seconds left on a timed level's clock
+    pub fn time_left(&self) -> Option<f32> {
+        self.level_rules.timed.map(|limit| (limit - self.level_time).max(0f32))
+    }
+
+    // Goes back to generated boards once a run ends, returning the screen the board was picked from
+    pub fn leave_board(&mut self) -> GameState {
+        match std::mem::replace(&mut self.board, BoardSource::Generated) {
+            BoardSource::Generated => GameState::Menu,
+            BoardSource::Editor(_) => GameState::Editor,
+            BoardSource::Campaign { .. } => GameState::LevelSelect,
+            BoardSource::Shared(_) => GameState::Menu,
+        }
+    }
+
+    // Turns a frame's real time into game time, which stops entirely during a hit-stop and runs
+    // faster while fast-forwarding
+    pub fn scaled_dt(&mut self, frame_time: f32) -> f32 {
+        if self.hit_stop > 0f32 {
+            self.hit_stop = (self.hit_stop - frame_time).max(0f32);
+            return 0f32;
+        }
+        if self.fast_forward {
+            return frame_time * FAST_FORWARD_SCALE;
+        }
+        frame_time
+    }
+
+    // Keeps the waiting ball resting on the middle of the paddle
+    fn hold_ball_on_paddle(&mut self) {
+        let paddle = self.paddle.rect;
+        if let Some(ball) = self.balls.first_mut() {
+            ball.rect.x = paddle.x + (paddle.w - ball.rect.w) * 0.5f32;
+            ball.rect.y = paddle.y - ball.rect.h - 1f32;
+            ball.vel = vec2(0f32, -1f32);
+        }
+    }
+
+    // Launches the ball off the paddle, raising the forgiveness floor if it's enabled or assist forces it
+    pub fn launch_ball(&mut self, settings: &Settings) {
+        self.awaiting_serve = false;
+        if let Some(ball) = self.balls.first_mut() {
+            ball.vel = vec2(rand::gen_range(-0.3f32, 0.3f32), -1f32).normalize();
+        }
+        if settings.forgiveness_floor_active() || self.modifiers.forgiveness_floor {
+            self.forgiveness_timer = FORGIVENESS_TIME;
+        }
+    }
+
+    // Drops any falling capsules and ends active effects
+    pub fn clear_power_ups(&mut self) {
+        self.power_ups.clear();
+        self.big_ball_timer = 0f32;
+        self.fireball_timer = 0f32;
+        self.split_timer = 0f32;
+        self.splits_left = 0;
+        for ball in self.balls.iter_mut() {
+            ball.fireball = false;
+        }
+    }
+
+    pub fn activate_power_up(&mut self, kind: PowerUpKind) {
+        match kind {
+            PowerUpKind::BigBall => self.big_ball_timer = BIG_BALL_TIME,
+            PowerUpKind::Fireball => self.fireball_timer = FIREBALL_TIME,
+            PowerUpKind::Split => {
+                self.split_timer = SPLIT_TIME;
+                self.splits_left = SPLITS_PER_POWER_UP;
+            },
+        }
+    }
+
+    // Changes the score by a signed amount, never going below zero
+    pub fn add_score(&mut self, delta: i32) {
+        self.score = (self.score + delta).max(0);
+        if delta < 0 {
+            self.score_flash = SCORE_FLASH_TIME;
+        }
+    }
+
+    // Eases the HUD score towards the real one, fast for a big jump and never slower than
+    // SCORE_COUNT_MIN_SPEED, landing on it exactly. Runs in real time so it keeps counting on the end screens
+    pub fn update_displayed_score(&mut self, dt: f32) {
+        let gap = self.score as f32 - self.displayed_score;
+        let step = (gap.abs() * SCORE_COUNT_RATE).max(SCORE_COUNT_MIN_SPEED) * dt;
+        if gap.abs() <= step {
+            self.displayed_score = self.score as f32;
+        } else {
+            self.displayed_score += step * gap.signum();
+        }
+    }
+
+    // Moves trap debris and stuns the paddle with any piece that reaches it
+    pub fn update_debris(&mut self, dt: f32) {
+        self.score_flash = (self.score_flash - dt).max(0f32);
+        for debris in self.debris.iter_mut() {
+            debris.update(dt);
+        }
+        let mut stunned = false;
+        let paddle_rect = self.paddle.rect;
+        self.debris.retain(|debris| {
+            if debris.rect.overlaps(&paddle_rect) {
+                stunned = true;
+                return false;
+            }
+            debris.rect.y < screen_height() && debris.rect.right() > 0f32 && debris.rect.x < screen_width()
+        });
+        if stunned {
+            self.paddle.stun();
+        }
+    }
+
+    // Moves falling capsules, applies the ones the paddle catches, and ticks active effects down
+    pub fn update_power_ups(&mut self, dt: f32) {
+        let mut caught = vec![];
+        for power_up in self.power_ups.iter_mut() {
+            power_up.update(dt);
+        }
+        self.power_ups.retain(|power_up| {
+            if power_up.rect.overlaps(&self.paddle.rect) {
+                caught.push(power_up.kind);
+                return false;
+            }
+            power_up.rect.y < screen_height()
+        });
+        for kind in caught {
+            self.activate_power_up(kind);
+            self.events.push(GameEvent::PowerUpCollected(kind));
+        }
+
+        self.big_ball_timer = (self.big_ball_timer - dt).max(0f32);
+        self.fireball_timer = (self.fireball_timer - dt).max(0f32);
+        self.split_timer = (self.split_timer - dt).max(0f32);
+        if self.split_timer <= 0f32 {
+            self.splits_left = 0;
+        }
+
+        // Keeps every ball, including ones spawned mid-effect, in line with the active effects
+        let size = if self.big_ball_timer > 0f32 { BIG_BALL_SIZE } else { BALL_SIZE };
+        let fireball = self.fireball_timer > 0f32;
+        for ball in self.balls.iter_mut() {
+            if ball.rect.w != size {
+                ball.set_size(size);
+            }
+            ball.fireball = fireball;
+            if fireball && dt > 0f32 {
+                self.particles.push(Particle::ember(ball.rect.point() + ball.rect.size() * 0.5f32));
+            }
+        }
+    }
+
+    // Damages blocks in reading order, top row first and left to right within a row, so several
+    // blocks breaking on the same frame always score, drop, and spawn in the same sequence
+    pub fn apply_block_hits(&mut self, mut hits: Vec<BlockHit>) {
+        sort_hits(&mut hits, &self.blocks);
+
+        // New balls with the velocity to give them, or None for a random downward one
+        let mut spawn_later: Vec<(Vec2, Option<Vec2>)> = vec![];
+        let max_balls = self.max_balls();
+        // Explosions queue more hits onto the end, so this walks by index while the list grows
+        let mut next = 0;
+        while next < hits.len() {
+            let hit = hits[next];
+            next += 1;
+            let block = &mut self.blocks[hit.block];
+            // Another ball already finished this block off earlier in the frame
+            if block.lives <= 0 {
+                continue;
+            }
+            block.reveal_timer = MEMORY_REVEAL_TIME;
+
+            // The split-off ball leaves at an angle to the one that hit, at the same speed. Only real hits
+            // split, so an explosion or a shattering cluster can't spend the whole budget at once
+            if !hit.lethal && self.splits_left > 0 && self.balls.len() + spawn_later.len() < max_balls {
+                let ball = &self.balls[hit.ball];
+                let (sin, cos) = SPLIT_ANGLE.sin_cos();
+                let vel = vec2(ball.vel.x * cos - ball.vel.y * sin, ball.vel.x * sin + ball.vel.y * cos);
+                spawn_later.push((ball.rect.point(), Some(vel)));
+                self.splits_left -= 1;
+            }
+
+            if !block.is_destructible() {
+                continue;
+            }
+
+            if block.lives == block.max_lives {
+                block.crack_origin = (hit.contact - block.rect.point()).clamp(Vec2::ZERO, block.rect.size());
+            }
+            block.lives -= if hit.lethal { block.lives } else { 1 };
+            if block.lives > 0 {
+                block.hit_flash_frames = BLOCK_HIT_FLASH_FRAMES;
+                for _ in 0..SPARK_COUNT {
+                    self.particles.push(Particle::spark(hit.contact, block.color()));
+                }
+            } else {
+                let (block_type, block_rect) = (block.block_type, block.rect);
+                if self.modifiers.memory {
+                    self.destroyed_outlines.push(block_rect);
+                }
+                self.events.push(GameEvent::BlockDestroyed {
+                    pos: block_rect.point(),
+                    block_type,
+                });
+                let center = block_rect.point() + block_rect.size() * 0.5f32;
+
+                // Traps don't score or build chains
+                if block_type == BlockType::Trap {
+                    self.add_score(-TRAP_PENALTY);
+                    self.popups.push(Popup::points(center, -TRAP_PENALTY));
+                    let paddle_center = self.paddle.rect.point() + self.paddle.rect.size() * 0.5f32;
+                    self.debris.push(Debris::new(center, paddle_center));
+                } else {
+                    self.combo = (self.combo + COMBO_FILL).min(1f32);
+                    self.add_score(BLOCK_POINTS * self.combo_multiplier());
+
+                    // Explosion hits carry the ball that set them off, so a chain reaction builds that ball's chain
+                    let ball = &mut self.balls[hit.ball];
+                    ball.chain = if self.elapsed - ball.last_destroy_time <= CHAIN_WINDOW { ball.chain + 1 } else { 1 };
+                    ball.last_destroy_time = self.elapsed;
+                    if ball.chain >= 2 {
+                        let length = ball.chain;
+                        let bonus = chain_bonus(length);
+                        self.add_score(bonus);
+                        self.longest_chain = self.longest_chain.max(length);
+                        self.events.push(GameEvent::Chain { length, bonus });
+
+                        self.popups.push(Popup::new(center, chain_text_id(length), length as i32, GOLD));
+                        self.popups.push(Popup::points(center + vec2(0f32, 28f32), bonus));
+                    }
+                }
+                let block = &self.blocks[hit.block];
+
+                if rand::gen_range(0f32, 1f32) < POWER_UP_DROP_CHANCE {
+                    let center = block.rect.point() + block.rect.size() * 0.5f32;
+                    self.power_ups.push(PowerUp::new(center, PowerUpKind::random()));
+                }
+
+                // Spawns a new ball if it is of the special block type
+                if block.block_type == BlockType::SpawnBallOnDeath {
+                    spawn_later.push((self.balls[hit.ball].rect.point(), None));
+                }
+
+                // Destroys the surrounding ring of blocks, reading order again so chains stay predictable
+                if block.block_type == BlockType::Explosive {
+                    let center = block.rect.point() + block.rect.size() * 0.5f32;
+                    let reach = block.rect.size() * 1.5f32;
+                    for (i, neighbor) in self.blocks.iter().enumerate() {
+                        let neighbor_center = neighbor.rect.point() + neighbor.rect.size() * 0.5f32;
+                        let offset = (neighbor_center - center).abs();
+                        if i != hit.block && neighbor.lives > 0 && offset.x < reach.x && offset.y < reach.y {
+                            hits.push(BlockHit {
+                                block: i,
+                                ball: hit.ball,
+                                contact: neighbor_center,
+                                lethal: true,
+                            });
+                        }
+                    }
+                }
+
+                // Queues a lethal hit on each brittle block in a grid cell sharing a side with this one. Those
+                // queue their own neighbors when they break, and a block queued twice is already broken by its
+                // second hit and skipped, so the cascade takes each block once and ends with the cluster
+                if block.block_type == BlockType::Brittle {
+                    let (column, row) = block.cell;
+                    for (i, neighbor) in self.blocks.iter().enumerate() {
+                        let neighbor_center = neighbor.rect.point() + neighbor.rect.size() * 0.5f32;
+                        let adjacent = neighbor.cell.0.abs_diff(column) + neighbor.cell.1.abs_diff(row) == 1;
+                        if adjacent && neighbor.block_type == BlockType::Brittle && neighbor.lives > 0 {
+                            hits.push(BlockHit {
+                                block: i,
+                                ball: hit.ball,
+                                contact: neighbor_center,
+                                lethal: true,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        let room = max_balls.saturating_sub(self.balls.len());
+        for (pos, vel) in spawn_later.into_iter().take(room) {
+            let mut ball = self.new_ball(pos);
+            if let Some(vel) = vel {
+                ball.vel = vel;
+            }
+            self.balls.push(ball);
+        }
+    }
+
+    // Creates a ball colored against the ones already in play. Colors come back as soon as their
+    // ball is lost, and only once every pastel is taken do they start repeating
+    pub fn new_ball(&self, pos: Vec2) -> Ball {
+        let color = if self.balls.is_empty() {
+            self.skin.ball
+        } else {
+            SPAWNED_BALL_COLORS
+                .iter()
+                .copied()
+                .find(|color| self.balls.iter().all(|ball| ball.color != *color))
+                .unwrap_or(SPAWNED_BALL_COLORS[(self.balls.len() - 1) % SPAWNED_BALL_COLORS.len()])
+        };
+        Ball::new(pos, color, self.ball_speed_scale())
+    }
+
+    pub fn new_paddle(&self) -> Paddle {
+        let enlarge = if self.enlarged { ENLARGE_WIDTH } else { 1f32 };
+        Paddle::new(PADDLE_SIZE.x * self.modifiers.paddle_width * enlarge)
+    }
+
+    pub fn serve_ball(&mut self) {
+        self.balls.clear();
+        self.awaiting_serve = false;
+        let ball = self.new_ball(vec2(screen_width() * 0.5f32 - BALL_SIZE * 0.5f32, screen_height() * 0.5f32));
+        self.balls.push(ball);
+    }
+
+    // Clears the board so the win check fires on the next frame
+    pub fn skip_level(&mut self) {
+        self.blocks.clear();
+    }
+
+    pub fn add_lives(&mut self, count: i32) {
+        self.player_lives += count;
+    }
+
+    // Spawns extra balls just above the paddle
+    pub fn spawn_balls(&mut self, count: usize) {
+        for _ in 0..count {
+            let ball = self.new_ball(
+                self.paddle.rect.point() + vec2(self.paddle.rect.w * 0.5f32 - BALL_SIZE * 0.5f32, -50f32),
+            );
+            self.balls.push(ball);
+        }
+    }
+
+    // Regenerates the current level from the given seed, keeping score and lives
+    pub fn set_seed(&mut self, seed: u64, settings: &Settings) {
+        self.seed = seed;
+        rand::srand(seed);
+        self.blocks.clear();
+        init_blocks(&mut self.blocks, settings);
+        self.serve_ball();
+        self.snapshot_level();
+    }
+
+    pub fn give_power_up(&mut self, name: &str) -> Result<(), String> {
+        let kind = PowerUpKind::from_name(name).ok_or_else(|| format!("no power-up named '{}'", name))?;
+        self.activate_power_up(kind);
+        Ok(())
+    }
+}
+
+// Resets the game after a player loses and wishes to play again
+// Any new per-run state needs clearing here too so a quick restart starts completely fresh
+fn reset_game(game: &mut Game, settings: &Settings) {
+    game.run_modifiers = Modifiers::from_settings(settings);
+    game.apply_level_rules();
+    game.enlarged = false;
+    game.shielded = false;
+    game.paddle = game.new_paddle();
+    game.score = 0;
+    game.player_lives = STARTING_LIVES;
+    game.restart_hold = 0f32;
+    game.run_restart_hold = 0f32;
+    game.life_lost_flash = 0f32;
+    game.hit_stop = 0f32;
+    game.forgiveness_timer = 0f32;
+    game.safety_net = settings.safety_net_active();
+    game.particles.clear();
+    game.popups.clear();
+    game.debris.clear();
+    game.score_flash = 0f32;
+    game.displayed_score = 0f32;
+    game.cheated = game.physics.is_modified();
+    game.continues_used = 0;
+    game.balls_unstuck = 0;
+    if let Some(heatmap) = game.heatmap.as_mut() {
+        heatmap.clear();
+    }
+    game.elapsed = 0f32;
+    game.blocks_destroyed = 0;
+    game.stats_recorded = None;
+    game.longest_chain = 0;
+    game.events.clear();
+    game.clear_power_ups();
+    // Each run draws its seed from the previous one, then reseeds so the board can be reproduced from it.
+    // A replay asked for on the end screen uses the last run's seed again instead
+    game.seed = game.replay_seed.take().unwrap_or_else(|| rand::rand() as u64);
+    rand::srand(game.seed);
+    game.serve_ball();
+    game.blocks.clear();
+    match &game.board {
+        BoardSource::Generated => init_blocks(&mut game.blocks, settings),
+        BoardSource::Editor(level) | BoardSource::Campaign { level, .. } => game.blocks = level.blocks_in_field(game.field),
+        // Already decoded once when it was pasted, so it can't fail here
+        BoardSource::Shared(code) => game.blocks = decode_level_in(code, game.field).unwrap_or_default(),
+    }
+    game.snapshot_level();
+}
+
+// Relative odds of each block type on a generated board, plus limits on the ones that could
+// make a board miserable if the dice ran hot
+struct BlockDistribution {
+    weights: [(BlockType, u32); 5],
+    max_indestructible: usize,
+    max_traps: usize,
+}
+
+impl BlockDistribution {
+    fn for_settings(settings: &Settings) -> Self {
+        let spawn_ball = if settings.spawn_ball_blocks { 10 } else { 0 };
+        let (explosive, indestructible, max_indestructible, trap, max_traps) = match settings.difficulty {
+            Difficulty::Easy => (8, 0, 0, 3, 1),
+            Difficulty::Normal => (5, 5, 3, 6, 3),
+            Difficulty::Hard => (5, 12, 6, 10, 5),
+        };
+        Self {
+            weights: [
+                (BlockType::Regular, 100 - spawn_ball - explosive - indestructible - trap),
+                (BlockType::SpawnBallOnDeath, spawn_ball),
+                (BlockType::Explosive, explosive),
+                (BlockType::Indestructible, indestructible),
+                (BlockType::Trap, trap),
+            ],
+            max_indestructible,
+            max_traps,
+        }
+    }
+
+    // Draws from the seeded RNG so a seed always produces the same board
+    fn sample(&self) -> BlockType {
+        let total: u32 = self.weights.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rand::gen_range(0, total);
+        for (block_type, weight) in self.weights.iter() {
+            if roll < *weight {
+                return *block_type;
+            }
+            roll -= weight;
+        }
+        BlockType::Regular
+    }
+}
+
+// The stretch of the screen between the side walls. Normally the full width, but with the fixed aspect
+// setting a window wider than FIELD_ASPECT gets empty margins either side instead of a wider field
+#[derive(Clone, Copy, PartialEq)]
+struct Field {
+    left: f32,
+    right: f32,
+}
+
+impl Field {
+    fn full() -> Self {
+        Self {
+            left: 0f32,
+            right: screen_width(),
+        }
+    }
+
+    fn from_settings(settings: &Settings) -> Self {
+        if !settings.fixed_aspect {
+            return Self::full();
+        }
+        let width = screen_width().min(screen_height() * FIELD_ASPECT);
+        let left = (screen_width() - width) * 0.5f32;
+        Self { left, right: left + width }
+    }
+
+    fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    fn center(&self) -> f32 {
+        (self.left + self.right) * 0.5f32
+    }
+}
+
+// Maps grid cells to screen space for a board of the given width in columns, centered and shrunk
+// as needed so the whole board fits between the walls. Anything placing blocks by column and row
+// goes through this so collision rects scale with the drawing
+struct BoardLayout {
+    origin: Vec2,
+    block_size: Vec2,
+    // Block size plus padding, the distance from one cell to the next
+    cell_size: Vec2,
+}
+
+impl BoardLayout {
+    fn fit(columns: usize) -> Self {
+        Self::fit_field(columns, Field::full(), BLOCK_PADDING)
+    }
+
+    fn fit_field(columns: usize, field: Field, padding: f32) -> Self {
+        let playfield = Rect::new(field.left + BOARD_MARGIN, 50f32, field.width() - BOARD_MARGIN * 2f32, f32::INFINITY);
+        Self::fit_in(columns, 1, playfield, padding)
+    }
+
+    // Fits a board of the given size into an area, centered across it and starting at its top, never
+    // drawing blocks larger than full size. `padding` is the gap between neighboring blocks at full size.
+    // There's no gap after the last column or row, so it isn't counted when fitting or centering
+    fn fit_in(columns: usize, rows: usize, area: Rect, padding: f32) -> Self {
+        let gap = vec2(padding, padding);
+        let full_size = vec2(columns as f32, rows as f32) * (BLOCK_SIZE + gap) - gap;
+        let scale = (area.w / full_size.x).min(area.h / full_size.y).min(1f32);
+        let cell_size = (BLOCK_SIZE + gap) * scale;
+        let width = cell_size.x * columns as f32 - padding * scale;
+        Self {
+            origin: vec2(area.x + (area.w - width) * 0.5f32, area.y),
+            block_size: BLOCK_SIZE * scale,
+            cell_size,
+        }
+    }
+
+    fn block(&self, column: usize, row: usize, block_type: BlockType) -> Block {
+        let pos = self.origin + vec2(column as f32, row as f32) * self.cell_size;
+        Block {
+            cell: (column, row),
+            ..Block::new(pos, self.block_size, block_type)
+        }
+    }
+}
+
+// Creates the board
+fn init_blocks(blocks: &mut Vec<Block>, settings: &Settings) {
+    let (width, height) = (6, 5);
+    let layout = BoardLayout::fit_field(width, Field::from_settings(settings), BLOCK_PADDING);
+
+    for i in 0..width * height {
+        blocks.push(layout.block(i % width, i / width, BlockType::Regular));
+    }
+
+    assign_block_types(blocks, &BlockDistribution::for_settings(settings));
+
+    if settings.portals {
+        let entry = rand::gen_range(0, blocks.len());
+        // Picks from the remaining blocks so the exit can never land on the entry
+        let mut exit = rand::gen_range(0, blocks.len() - 1);
+        if exit >= entry {
+            exit += 1;
+        }
+        blocks[entry].block_type = BlockType::Portal(0);
+        blocks[exit].block_type = BlockType::Portal(0);
+    }
+}
+
+// Gives every block a type drawn from the distribution, the same block never twice, holding indestructible
+// blocks and traps to their limits
+fn assign_block_types(blocks: &mut [Block], distribution: &BlockDistribution) {
+    let (mut indestructible, mut traps) = (0, 0);
+    for block in blocks.iter_mut() {
+        let mut block_type = distribution.sample();
+        if block_type == BlockType::Indestructible {
+            if indestructible >= distribution.max_indestructible {
+                block_type = BlockType::Regular;
+            } else {
+                indestructible += 1;
+            }
+        }
+        if block_type == BlockType::Trap {
+            if traps >= distribution.max_traps {
+                block_type = BlockType::Regular;
+            } else {
+                traps += 1;
+            }
+        }
+        block.block_type = block_type;
+    }
+
+    // Turns traps back into regular blocks until clearing the board is worth more than the traps take away
+    let board_value = |blocks: &[Block]| -> i32 {
+        blocks
+            .iter()
+            .map(|block| match block.block_type {
+                BlockType::Trap => -TRAP_PENALTY,
+                _ if block.is_destructible() => BLOCK_POINTS,
+                _ => 0,
+            })
+            .sum()
+    };
+    while board_value(blocks) <= 0 {
+        let Some(trap) = blocks.iter_mut().find(|block| block.block_type == BlockType::Trap) else {
+            break;
+        };
+        trap.block_type = BlockType::Regular;
+    }
+}
+
+fn draw_title_text(text: &str, font: Font) {
+    draw_title_text_scaled(text, font, 1f32);
+}
+
+// Scales the glyphs rather than the font size so animating doesn't rasterize a new size every frame
+fn draw_title_text_scaled(text: &str, font: Font, scale: f32) {
+    draw_title_text_ex(text, font, scale, WHITE);
+}
+
+fn draw_title_text_ex(text: &str, font: Font, scale: f32, color: Color) {
+    let dims = measure_text(text, Some(font), 50u16, scale);
+    draw_text_ex(
+        text,
+        screen_width() * 0.5f32 - dims.width * 0.5f32,
+        screen_height() * 0.5f32 - dims.height * 0.5f32,
+        TextParams {
+            font,
+            font_size: 50u16,
+            font_scale: scale,
+            color,
+            ..Default::default()
+        },
+    );
+}
+
+// Three stars in a row under the win headline, the earned ones gold. Each pops in STAR_POP_INTERVAL
+// after the one before, counting from `time` zero
+fn draw_star_rating(stars: u8, time: f32) {
+    let center = vec2(screen_width() * 0.5f32, screen_height() * 0.5f32 + 70f32);
+    for i in 0..3 {
+        let scale = ease_out_back((time - i as f32 * STAR_POP_INTERVAL) / END_TEXT_ANIMATION_TIME);
+        let color = if i < stars { GOLD } else { Color::new(1f32, 1f32, 1f32, 0.2f32) };
+        draw_star(center + vec2((i as f32 - 1f32) * STAR_RADIUS * 2.5f32, 0f32), STAR_RADIUS * scale, color);
+    }
+}
+
+// A filled five-pointed star, point up
+fn draw_star(center: Vec2, radius: f32, color: Color) {
+    if radius <= 0f32 {
+        return;
+    }
+    let point = |i: u32| {
+        let angle = i as f32 * std::f32::consts::PI / 5f32;
+        let length = if i.is_multiple_of(2) { radius } else { radius * 0.45f32 };
+        center + vec2(angle.sin(), -angle.cos()) * length
+    };
+    for i in 0..10 {
+        draw_triangle(center, point(i), point(i + 1), color);
+    }
+}
+
+// Grows from nothing to slightly past full size, then settles back to 1
+fn ease_out_back(t: f32) -> f32 {
+    let overshoot = 1.70158f32;
+    let t = t.clamp(0f32, 1f32) - 1f32;
+    1f32 + (overshoot + 1f32) * t * t * t + overshoot * t * t
+}
+
+// Draws a line of smaller text centered horizontally at the given height
+fn draw_centered_text(text: &str, y: f32, font: Font) {
+    let dims = measure_text(text, Some(font), 30u16, 1.0f32);
+    draw_text_ex(
+        text,
+        screen_width() * 0.5f32 - dims.width * 0.5f32,
+        y,
+        TextParams {
+            font,
+            font_size: 30u16,
+            color: WHITE,
+            ..Default::default()
+        },
+    );
+}
+
+// Lists every setting with its value, highlighting the selected row
+fn draw_settings(settings: &Settings, menu: &Menu<usize>, lang: &Lang, font: Font) {
+    draw_centered_text(lang.tr("settings.title"), 100f32, font);
+
+    let labels: Vec<String> = settings
+        .entries()
+        .iter()
+        .map(|(label, value)| format!("{}: < {} >", lang.tr(label), lang.tr(value)))
+        .collect();
+    // The help line sits at the bottom, and the list scrolls rather than running into it
+    menu.draw_scrolling(&labels, 160f32, 38f32, screen_height() - 110f32, font);
+
+    draw_centered_text(lang.tr("settings.help"), screen_height() - 60f32, font);
+}
+
+fn draw_stats(stats: &Stats, lang: &Lang, font: Font) {
+    draw_centered_text(lang.tr("stats.title"), 100f32, font);
+
+    for (i, (label, value)) in stats.entries().iter().enumerate() {
+        draw_centered_text(&format!("{}: {}", lang.tr(label), value), 180f32 + i as f32 * 40f32, font);
+    }
+
+    draw_centered_text(lang.tr("back"), screen_height() - 60f32, font);
+}
+
+fn draw_leaderboard(leaderboard: &Leaderboard, lang: &Lang, font: Font) {
+    draw_centered_text(lang.tr("leaderboard.title"), 100f32, font);
+
+    if leaderboard.entries.is_empty() {
+        draw_centered_text(lang.tr("leaderboard.empty"), 180f32, font);
+    }
+    for (i, entry) in leaderboard.entries.iter().enumerate() {
+        let mut line = format!("{}. {}  {}", i + 1, entry.initials, leaderboard::format_time(entry.time));
+        if entry.assisted {
+            line = format!("{} {}", line, lang.tr("leaderboard.assisted"));
+        }
+        if entry.continued {
+            line = format!("{} {}", line, lang.tr("leaderboard.continued"));
+        }
+        if !entry.mutators.is_empty() {
+            let names: Vec<&str> = entry.mutators.iter().map(|mutator| lang.tr(mutator.text_id())).collect();
+            line = format!("{} [{}]", line, names.join(", "));
+        }
+        draw_centered_text(&line, 180f32 + i as f32 * 40f32, font);
+    }
+
+    draw_centered_text(lang.tr("back"), screen_height() - 60f32, font);
+}
+
+// The picker under the win message, with the letter being changed highlighted
+fn draw_initials_entry(entry: &InitialsEntry, time: f32, lang: &Lang, font: Font) {
+    let y = screen_height() * 0.5f32 + 60f32;
+    let heading = lang.tr("leaderboard.new_time").replace("{time}", &leaderboard::format_time(time));
+    draw_centered_text(&heading, y, font);
+
+    let letter_w = 40f32;
+    let start_x = screen_width() * 0.5f32 - letter_w * entry.letters.len() as f32 * 0.5f32;
+    for (i, letter) in entry.letters.iter().enumerate() {
+        draw_text_ex(
+            &(*letter as char).to_string(),
+            start_x + i as f32 * letter_w + 10f32,
+            y + 60f32,
+            TextParams {
+                font,
+                font_size: 40u16,
+                color: if i == entry.cursor { YELLOW } else { WHITE },
+                ..Default::default()
+            },
+        );
+    }
+
+    draw_centered_text(lang.tr("leaderboard.entry_help"), y + 110f32, font);
+}
+
+// Lists the global top 10 down the right edge of the menu, or a small offline marker if the server can't be reached
+#[cfg(feature = "online")]
+fn draw_online_scores(online: &online::Online, lang: &Lang, font: Font) {
+    let params = TextParams {
+        font,
+        font_size: 20u16,
+        color: WHITE,
+        ..Default::default()
+    };
+    let x = screen_width() - 200f32;
+
+    if online.offline {
+        draw_text_ex(lang.tr("online.offline"), x, 30f32, TextParams { color: GRAY, ..params });
+        return;
+    }
+
+    draw_text_ex(lang.tr("online.top"), x, 30f32, params);
+    for (i, (initials, score)) in online.top_scores.iter().enumerate() {
+        draw_text_ex(&format!("{:>2}. {} {}", i + 1, initials, score), x, 60f32 + i as f32 * 24f32, params);
+    }
+}
+
+// The Fog mutator's lighting pass over the finished board: the playfield is dimmed down to FOG_BRIGHTNESS
+// except in a pool of light around each ball and a band along the paddle's row. The dimming is a grid of
+// cells, each shaded by its distance to the nearest light so the edges fall off softly
+fn draw_fog(game: &Game) {
+    let lights: Vec<Vec2> = game.balls.iter().map(|ball| ball.rect.point() + ball.rect.size() * 0.5f32).collect();
+    let row_y = game.paddle.rect.y + game.paddle.rect.h * 0.5f32;
+    // A cell past the edges on every side, so screen shake never shows an unlit strip
+    let (columns, rows) = ((screen_width() / FOG_CELL) as i32 + 2, (screen_height() / FOG_CELL) as i32 + 2);
+    for row in -1..rows {
+        for column in -1..columns {
+            let pos = vec2(column as f32, row as f32) * FOG_CELL;
+            let center = pos + vec2(FOG_CELL, FOG_CELL) * 0.5f32;
+            let ball_distance = lights.iter().map(|light| center.distance(*light)).fold(f32::INFINITY, f32::min) - FOG_LIGHT_RADIUS;
+            let row_distance = (center.y - row_y).abs() - FOG_ROW_HALF_HEIGHT;
+            let darkness = (ball_distance.min(row_distance) / FOG_FALLOFF).clamp(0f32, 1f32);
+            if darkness > 0f32 {
+                draw_rectangle(pos.x, pos.y, FOG_CELL, FOG_CELL, Color::new(0f32, 0f32, 0f32, (1f32 - FOG_BRIGHTNESS) * darkness));
+            }
+        }
+    }
+}
+
+// A red strip across the screen with its bottom edge at `bottom`
+fn draw_warning_banner(text: &str, bottom: f32) {
+    draw_rectangle(0f32, bottom - 40f32, screen_width(), 40f32, Color::new(0.6f32, 0f32, 0f32, 0.8f32));
+    draw_text(text, 10f32, bottom - 14f32, 24f32, WHITE);
+}
+
+// Draws a modal box in the middle of the screen holding a single line of text
+fn draw_prompt(text: &str, font: Font) {
+    let dims = measure_text(text, Some(font), 30u16, 1.0f32);
+    let (box_w, box_h) = (dims.width + 60f32, dims.height + 60f32);
+    let (box_x, box_y) = (screen_width() * 0.5f32 - box_w * 0.5f32, screen_height() * 0.5f32 - box_h * 0.5f32);
+
+    draw_rectangle(box_x, box_y, box_w, box_h, Color::new(0f32, 0f32, 0f32, 0.85f32));
+    draw_rectangle_lines(box_x, box_y, box_w, box_h, 2f32, WHITE);
+    draw_text_ex(
+        text,
+        screen_width() * 0.5f32 - dims.width * 0.5f32,
+        screen_height() * 0.5f32 + dims.height * 0.5f32,
+        TextParams {
+            font,
+            font_size: 30u16,
+            color: WHITE,
+            ..Default::default()
+        },
+    );
+}
+
+// Draws the title, a progress bar of loaded assets, and any that failed to load
+fn draw_loading_screen(loader: &AssetLoader, lang: &Lang, font: Font) {
+    draw_title_text(lang.tr("title"), font);
+
+    let (bar_w, bar_h) = (screen_width() * 0.5f32, 16f32);
+    let (bar_x, bar_y) = (screen_width() * 0.25f32, screen_height() * 0.5f32 + 40f32);
+    let progress = loader.loaded() as f32 / loader.total() as f32;
+    draw_rectangle(bar_x, bar_y, bar_w * progress, bar_h, WHITE);
+    draw_rectangle_lines(bar_x, bar_y, bar_w, bar_h, 2f32, WHITE);
+
+    let params = TextParams {
+        font,
+        font_size: 24u16,
+        color: WHITE,
+        ..Default::default()
+    };
+    let progress = lang.tr("loading.progress")
+        .replace("{n}", &loader.loaded().to_string())
+        .replace("{total}", &loader.total().to_string());
+    draw_text_ex(&progress, bar_x, bar_y + 45f32, params);
+
+    if loader.is_done() && !loader.failures.is_empty() {
+        let mut y = bar_y + 85f32;
+        for path in loader.failures.iter() {
+            let text = lang.tr("loading.failed").replace("{path}", path);
+            draw_text_ex(&text, bar_x, y, TextParams { color: ORANGE, ..params });
+            y += 30f32;
+        }
+        draw_text_ex(lang.tr("loading.continue"), bar_x, y + 10f32, params);
+    }
+}
+
+// The combo meter in the top right, with the multiplier it's worth next to it. Hidden while empty
+fn draw_combo_meter(game: &Game, font: Font) {
+    if game.combo <= 0f32 {
+        return;
+    }
+    let (bar_w, bar_h) = (200f32, 10f32);
+    let (bar_x, bar_y) = (screen_width() - bar_w - 30f32, 30f32);
+    let color = if game.combo_multiplier() >= COMBO_MAX_MULTIPLIER { GOLD } else { ORANGE };
+    draw_rectangle(bar_x, bar_y, bar_w, bar_h, Color::new(1f32, 1f32, 1f32, 0.2f32));
+    draw_rectangle(bar_x, bar_y, bar_w * game.combo, bar_h, color);
+
+    let text = format!("x{}", game.combo_multiplier());
+    let dims = measure_text(&text, Some(font), 24u16, 1f32);
+    draw_text_ex(
+        &text,
+        bar_x - dims.width - 10f32,
+        bar_y + bar_h,
+        TextParams {
+            font,
+            font_size: 24u16,
+            color,
+            ..Default::default()
+        },
+    );
+}
+
+// Focus left, under the lives. Dimmed once it's empty, since Ctrl does nothing until some comes back
+fn draw_focus_bar(game: &Game) {
+    let (bar_x, bar_y, bar_w, bar_h) = (30f32, 80f32, 120f32, 8f32);
+    let color = if game.focusing {
+        SKYBLUE
+    } else if game.focus > 0f32 {
+        WHITE
+    } else {
+        GRAY
+    };
+    draw_rectangle(bar_x, bar_y, bar_w, bar_h, Color::new(1f32, 1f32, 1f32, 0.2f32));
+    draw_rectangle(bar_x, bar_y, bar_w * game.focus, bar_h, color);
+}
+
+// Thin bar under the score showing how much of the level has been cleared, flashing when it fills
+fn draw_level_progress(game: &Game) {
+    let (bar_w, bar_h) = (screen_width() * 0.3f32, 6f32);
+    let (bar_x, bar_y) = (screen_width() * 0.5f32 - bar_w * 0.5f32, 52f32);
+    let flashing = game.effects.flashes && game.progress_flash > 0f32 && (game.progress_flash * 10f32) as i32 % 2 == 0;
+    let color = if flashing { GOLD } else { WHITE };
+
+    draw_rectangle(bar_x, bar_y, bar_w, bar_h, Color::new(1f32, 1f32, 1f32, 0.2f32));
+    draw_rectangle(bar_x, bar_y, bar_w * game.progress_shown, bar_h, color);
+    // Without flashes, a gold outline fades out around the bar instead of it blinking
+    if !game.effects.flashes && game.progress_flash > 0f32 {
+        let alpha = game.progress_flash / PROGRESS_FLASH_TIME;
+        draw_rectangle_lines(bar_x - 3f32, bar_y - 3f32, bar_w + 6f32, bar_h + 6f32, 2f32, Color::new(1f32, 0.84f32, 0f32, alpha));
+    }
+}
+
+// The fastest ball's speed and its heading in degrees from straight up, small and centered under the level
+// progress bar. Reads zero during a hit-stop, since the balls aren't moving then
+fn draw_ball_speed(game: &Game, font: Font) {
+    let fastest = game.balls.iter().max_by(|a, b| a.speed().total_cmp(&b.speed()));
+    let speed = if game.hit_stop > 0f32 { 0f32 } else { fastest.map_or(0f32, |ball| ball.speed()) };
+    let angle = fastest.map_or(0f32, |ball| ball.vel.x.atan2(-ball.vel.y).to_degrees());
+    let text = format!("{:.0} px/s  {:+.0} deg", speed, angle);
+    let dims = measure_text(&text, Some(font), 20u16, 1f32);
+    draw_text_ex(
+        &text,
+        screen_width() * 0.5f32 - dims.width * 0.5f32,
+        78f32,
+        TextParams {
+            font,
+            font_size: 20u16,
+            color: GRAY,
+            ..Default::default()
+        },
+    );
+}
+
+// Draws the score, lives, and restart progress over the board
+fn draw_hud(game: &Game, lang: &Lang, font: Font) {
+    let score_text = lang.tr("hud.score").replace("{score}", &(game.displayed_score.round() as i32).to_string());
+    let score_text_dim = measure_text(&score_text, Some(font), 30u16, 1.0);
+
+    // Displays the score at the top of the screen
+    draw_text_ex(
+        &score_text,
+        screen_width() * 0.5f32 - score_text_dim.width * 0.5f32,
+        40.0,
+        TextParams {
+            font,
+            font_size: 30u16,
+            color: if game.score_flash > 0f32 { RED } else { WHITE },
+            ..Default::default()
+        },
+    );
+    if game.fast_forward {
+        draw_fast_forward_icon(vec2(screen_width() * 0.5f32 + score_text_dim.width * 0.5f32 + 14f32, 29f32));
+    }
+
+    // Displays the player's remaining lives at the top of the screen
+    draw_text_ex(
+        &lang.tr("hud.lives").replace("{lives}", &game.player_lives.to_string()),
+        30.0,
+        40.0,
+        TextParams {
+            font,
+            font_size: 30u16,
+            color: WHITE,
+            ..Default::default()
+        },
+    );
+
+    if game.safety_net_armed {
+        draw_text_ex(
+            lang.tr("hud.safety_net"),
+            30.0,
+            66.0,
+            TextParams {
+                font,
+                font_size: 20u16,
+                color: GREEN,
+                ..Default::default()
+            },
+        );
+    }
+
+    draw_level_progress(game);
+    draw_combo_meter(game, font);
+    draw_focus_bar(game);
+
+    // Under the combo meter, so anyone watching can tell the run is assisted or which mutators are on
+    let mut badges = Vec::new();
+    if game.modifiers.assisted {
+        badges.push((lang.tr("hud.assist").to_string(), SKYBLUE));
+    }
+    if !game.level_rules.is_empty() {
+        badges.push((lang.tr("hud.challenge").to_string(), RED));
+    }
+    if let Some(time_left) = game.time_left() {
+        let color = if time_left < 10f32 { RED } else { WHITE };
+        badges.push((lang.tr("hud.time_left").replace("{time}", &leaderboard::format_time(time_left)), color));
+    }
+    if !game.modifiers.mutators.is_empty() {
+        let names: Vec<&str> = game.modifiers.mutators.iter().map(|mutator| lang.tr(mutator.text_id())).collect();
+        badges.push((format!("{} x{:.2}", names.join(", "), game.modifiers.score_multiplier), ORANGE));
+    }
+    for (i, (text, color)) in badges.iter().enumerate() {
+        let dims = measure_text(text, Some(font), 20u16, 1f32);
+        draw_text_ex(
+            text,
+            screen_width() - dims.width - 30f32,
+            66.0 + i as f32 * 22.0,
+            TextParams {
+                font,
+                font_size: 20u16,
+                color: *color,
+                ..Default::default()
+            },
+        );
+    }
+
+    // Fills up while R or Shift+R is held so the player can see the restart coming
+    let progress = (game.restart_hold / RESTART_HOLD_TIME)
+        .max(game.run_restart_hold / RUN_RESTART_HOLD_TIME)
+        .min(1f32);
+    if progress > 0f32 {
+        draw_rectangle(0f32, screen_height() - 8f32, screen_width() * progress, 8f32, WHITE);
+    }
+}
+
+// Two arrowheads pointing right, left edge at `pos.x` and centered on `pos.y`
+fn draw_fast_forward_icon(pos: Vec2) {
+    let (width, half_height) = (10f32, 8f32);
+    for i in 0..2 {
+        let x = pos.x + i as f32 * width;
+        draw_triangle(vec2(x, pos.y - half_height), vec2(x, pos.y + half_height), vec2(x + width, pos.y), WHITE);
+    }
+}
+
+// The playfield is drawn in fixed layers, each in its own function, back to front:
+//   1. walls: the field edges, the danger line, and the forgiveness strip along the floor
+//   2. board: the paddle, Memory outlines, and the blocks
+//   3. pickups: falling power-ups and trap debris
+//   4. balls: each with its trail, then the landing markers and debug labels that belong to them
+//   5. particles
+//   6. fog, which covers everything so far
+//   7. floating text: score popups, above the fog so feedback always reads
+// The background goes underneath before this, and the HUD, state screens, and overlays like prompts,
+// warnings, and the console go on top after it, in screen space so camera shake doesn't move them
+fn draw_world(game: &Game, settings: &Settings, game_state: GameState, debug_overlay: bool, lang: &Lang, font: Font) {
+    draw_walls_layer(game, settings);
+    draw_board_layer(game);
+    draw_pickups_layer(game);
+    draw_balls_layer(game, settings, game_state, debug_overlay, font);
+    draw_particles_layer(game);
+    if game.modifiers.fog && matches!(game_state, GameState::Game | GameState::Paused) {
+        draw_fog(game);
+    }
+    draw_floating_text_layer(game, lang, font);
+}
+
+fn draw_walls_layer(game: &Game, settings: &Settings) {
+    // With margins either side, the walls are drawn so it's clear where the ball turns back, or dashed
+    // where it wraps around instead
+    if game.field != Field::full() {
+        let color = Color::new(1f32, 1f32, 1f32, 0.25f32);
+        let dash = if game.modifiers.wrap_sides { WRAP_DASH_LENGTH } else { screen_height() };
+        let mut y = 0f32;
+        while y < screen_height() {
+            draw_line(game.field.left, y, game.field.left, y + dash, 2f32, color);
+            draw_line(game.field.right, y, game.field.right, y + dash, 2f32, color);
+            y += dash * 2f32;
+        }
+    }
+
+    if settings.danger_line {
+        let danger_y = game.paddle.rect.y - DANGER_LINE_OFFSET;
+        draw_line(0f32, danger_y, screen_width(), danger_y, 2f32, Color::new(1f32, 0.3f32, 0.3f32, 0.3f32));
+    }
+
+    if game.forgiveness_timer > 0f32 {
+        let alpha = 0.6f32 * game.forgiveness_timer / FORGIVENESS_TIME;
+        draw_rectangle(0f32, screen_height() - 6f32, screen_width(), 6f32, Color::new(0.4f32, 0.8f32, 1f32, alpha));
+    }
+}
+
+fn draw_board_layer(game: &Game) {
+    game.paddle.draw(game.skin);
+
+    // Where blocks used to be, so a Memory board can still be pieced together
+    for rect in game.destroyed_outlines.iter() {
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 1f32, Color::new(1f32, 1f32, 1f32, 0.12f32));
+    }
+
+    let board_alpha = game.board_alpha();
+    for block in game.blocks.iter() {
+        block.draw_with(&game.effects, board_alpha);
+    }
+}
+
+fn draw_pickups_layer(game: &Game) {
+    for power_up in game.power_ups.iter() {
+        power_up.draw();
+    }
+
+    for debris in game.debris.iter() {
+        debris.draw();
+    }
+}
+
+fn draw_balls_layer(game: &Game, settings: &Settings, game_state: GameState, debug_overlay: bool, font: Font) {
+    for ball in game.balls.iter() {
+        ball.draw(game.skin);
+    }
+
+    // A faint mark on the paddle's row where each falling ball will come down, firming up as it gets close
+    let landing_marker = settings.landing_marker || game.modifiers.landing_marker;
+    if landing_marker && matches!(game_state, GameState::Game) && !game.awaiting_serve {
+        for ball in game.balls.iter().filter(|ball| ball.vel.y > 0f32) {
+            if let Some(landing) = predict_crossing(ball, &game.blocks, game.paddle.rect.y, game.field, game.modifiers.wrap_sides, game.level_rules.gravity) {
+                let alpha = 0.15f32 + 0.35f32 * (1f32 - landing.time / PREDICTION_TIME);
+                let width = ball.rect.w;
+                draw_rectangle(landing.x - width * 0.5f32, game.paddle.rect.y - 6f32, width, 4f32, Color::new(1f32, 1f32, 1f32, alpha));
+            }
+        }
+    }
+
+    if debug_overlay {
+        for (i, ball) in game.balls.iter().enumerate() {
+            ball.draw_label(i, font);
+        }
+    }
+}
+
+fn draw_particles_layer(game: &Game) {
+    for particle in game.particles.iter() {
+        particle.draw();
+    }
+}
+
+fn draw_floating_text_layer(game: &Game, lang: &Lang, font: Font) {
+    for popup in game.popups.iter() {
+        popup.draw(lang, font);
+    }
+}
+
+// Under the end screen text, where the run's balls went, while the debug overlay is on
+fn draw_heatmap_overlay(game: &Game, debug_overlay: bool) {
+    if let Some(heatmap) = game.heatmap.as_ref().filter(|_| debug_overlay) {
+        heatmap.draw_overlay();
+    }
+}
+
+// Under the end screen text on a generated board, naming the seed S would replay
+fn draw_replay_hint(game: &Game, lang: &Lang, font: Font) {
+    if matches!(game.board, BoardSource::Generated) {
+        let text = lang.tr("game.replay").replace("{seed}", &game.seed.to_string());
+        draw_centered_text(&text, screen_height() * 0.5f32 + 100f32, font);
+    }
+}
+
+// Checked as each run starts, so turning the setting on or off applies from the next run
+fn start_session_log(game: &Game, settings: &Settings) -> Option<SessionLog> {
+    if settings.log_sessions || std::env::args().any(|arg| arg == "--log-session") {
+        SessionLog::create(game, settings)
+    } else {
+        None
+    }
+}
+
+// Window size, fullscreen and the title come from the settings file, so they're read before the window
+// opens, along with the icon from res/
+fn window_conf() -> Conf {
+    let settings = Settings::load();
+    Conf {
+        window_title: settings.window_title.clone(),
+        icon: assets::load_window_icon(),
+        window_width: settings.window_width,
+        window_height: settings.window_height,
+        fullscreen: settings.fullscreen,
+        window_resizable: true,
+        high_dpi: true,
+        ..Default::default()
+    }
+}
+
+#[macroquad::main(window_conf)]
+async fn main() {
+    let mut assets = Assets::new();
+    let mut loader = AssetLoader::new();
+    // Counts down while the missing-font banner is shown
+    let mut font_warning = 0f32;
+    // The announcer's current word and the seconds it has left, and the time until it can speak again
+    let mut announcement: Option<(&'static str, f32)> = None;
+    let mut announcement_cooldown = 0f32;
+    let mut game_state = GameState::Loading;
+    let mut prompt: Option<Prompt> = None;
+
+    let mut settings = Settings::load();
+    let mut lang = Lang::load(&settings.language).await;
+    let mut stats = Stats::load();
+    let mut music = MusicDirector::load().await;
+    let mut sounds = SoundManager::load().await;
+    let mut leaderboard = Leaderboard::load();
+    // Set on the win screen while a qualifying clear time is waiting for initials
+    let mut initials_entry: Option<InitialsEntry> = None;
+    let mut main_menu = Menu::new(MenuEntry::ALL.to_vec());
+    // One entry per row on the settings screen, by index into Settings::entries
+    let mut settings_menu = Menu::new((0..settings.entries().len()).collect());
+    let mut pause_menu = Menu::new(vec![PauseEntry::Resume, PauseEntry::Quit]);
+    let mut editor = Editor::new();
+    let (campaign, skipped_level) = level::load_campaign().await;
+    // Set with a countdown when a level was refused, starting with a campaign level that couldn't be read
+    let mut level_warning: Option<(LevelError, f32)> = skipped_level.map(|error| (error, LEVEL_WARNING_TIME));
+    let mut progress = Progress::load();
+    let mut level_select = LevelSelect::new();
+    let mut customize = Customize::new();
+    let mut mutators_screen = MutatorsScreen::new();
+    let mut interlude = Interlude::new();
+
+    let mut game = Game::new(&settings);
+    if std::env::args().any(|arg| arg == "--heatmap") {
+        game.heatmap = Some(Box::new(Heatmap::new()));
+    }
+    let mut console = Console::new();
+    let mut camera_fx = CameraFx::new();
+    // Swapped for a fresh one whenever the board calls for a different theme
+    let mut theme = Theme::Starfield;
+    let mut background = theme.create();
+    // Toggled with F3, labels each ball and shows a line of run internals
+    let mut debug_overlay = false;
+    let mut tuning_panel = TuningPanel::new();
+    // Toggled with F4, shows the ball speed under the score. The debug overlay shows it too
+    let mut show_ball_speed = false;
+    // Space both confirms menus and serves, buffered so an early press isn't lost
+    let mut space = InputBuffer::new(KeyCode::Space);
+    let mut frame_advance = FrameAdvance::new(std::env::args().any(|arg| arg == "--debug"));
+
+    let mut session_log: Option<SessionLog> = None;
+    #[cfg(feature = "online")]
+    let mut online = online::Online::new(&settings.online_url);
+    let mut last_state = game_state;
+    // Seconds since the current state was entered, driving the end screen headline animation
+    let mut state_time = 0f32;
+
+    loop {
+        let frame_start = get_time();
+        // Until the font has loaded, text is drawn with the built-in one
+        let font = assets.font.unwrap_or_default();
+
+        space.update();
+        game.effects = EffectsPolicy::from_settings(&settings);
+        if settings.fixed_aspect {
+            game.follow_field(Field::from_settings(&settings));
+        } else {
+            game.field = Field::full();
+        }
+
+        if console.update(&mut game, &settings) {
+            // The console owns the keyboard while it's open, freezing the game underneath
+            space.clear();
+        } else if let Some(active) = prompt {
+            // An open prompt captures Y/N/Escape so the state underneath doesn't also react to them
+            space.clear();
+            if is_key_pressed(KeyCode::Y) {
+                match active {
+                    Prompt::QuitToMenu => {
+                        session_log = None;
+                        game_state = game.leave_board();
+                        reset_game(&mut game, &settings);
+                    },
+                    // Leaving the loop lets main return normally instead of killing the window
+                    Prompt::ExitGame => break,
+                }
+                prompt = None;
+            } else if is_key_pressed(KeyCode::N) || is_key_pressed(KeyCode::Escape) {
+                prompt = None;
+            }
+        } else {
+            if is_key_pressed(KeyCode::F3) {
+                debug_overlay = !debug_overlay;
+            }
+            if debug_overlay && matches!(game_state, GameState::Game | GameState::Paused) && tuning_panel.update(&mut game.physics) {
+                game.cheated = true;
+            }
+            if is_key_pressed(KeyCode::F4) {
+                show_ball_speed = !show_ball_speed;
+            }
+            // Copies the board as it stands, so a code can capture a half-cleared level too
+            if is_key_pressed(KeyCode::F6) && matches!(game_state, GameState::Game) {
+                clipboard_set(&encode_level(&game.blocks, game.board_padding()));
+                let pos = game.paddle.rect.point() + vec2(game.paddle.rect.w * 0.5f32, -40f32);
+                game.popups.push(Popup::new(pos, "popup.code_copied", 0, WHITE));
+            }
+
+            match game_state {
+                GameState::Loading => {
+                    if !loader.is_done() {
+                        loader.load_next(&mut assets).await;
+                    // Failed assets stay on screen until the player chooses to carry on with the fallbacks
+                    } else if loader.failures.is_empty() || space.consume() {
+                        if assets.font.is_none() {
+                            font_warning = FONT_WARNING_TIME;
+                        }
+                        game_state = GameState::Menu;
+                    }
+                },
+                GameState::Menu => {
+                    let confirm = space.consume() || is_key_pressed(KeyCode::Enter);
+                    // The old single-key shortcuts still jump straight to their entry
+                    let shortcut = if is_key_pressed(KeyCode::P) {
+                        Some(MenuEntry::Practice)
+                    } else if is_key_pressed(KeyCode::S) {
+                        Some(MenuEntry::Settings)
+                    } else if is_key_pressed(KeyCode::T) {
+                        Some(MenuEntry::Stats)
+                    } else if is_key_pressed(KeyCode::L) {
+                        Some(MenuEntry::Leaderboard)
+                    } else if is_key_pressed(KeyCode::E) {
+                        Some(MenuEntry::Editor)
+                    } else if is_key_pressed(KeyCode::Escape) {
+                        Some(MenuEntry::Quit)
+                    } else {
+                        None
+                    };
+
+                    match main_menu.handle_input(confirm).or(shortcut) {
+                        Some(entry @ (MenuEntry::Campaign | MenuEntry::Practice)) => {
+                            game.mode = if matches!(entry, MenuEntry::Practice) { GameMode::Practice } else { GameMode::Campaign };
+                            session_log = start_session_log(&game, &settings);
+                            game_state = GameState::Game;
+                        },
+                        Some(MenuEntry::LevelSelect) => game_state = GameState::LevelSelect,
+                        None if is_key_pressed(KeyCode::V) => {
+                            // Shared codes are practice runs, like test plays from the editor
+                            let pasted = clipboard_get().ok_or(LevelError::InvalidCode);
+                            match pasted.and_then(|code| decode_level(&code).map(|_| code)) {
+                                Ok(code) => {
+                                    game.board = BoardSource::Shared(code);
+                                    game.mode = GameMode::Practice;
+                                    reset_game(&mut game, &settings);
+                                    session_log = start_session_log(&game, &settings);
+                                    game_state = GameState::Game;
+                                },
+                                Err(error) => level_warning = Some((error, LEVEL_WARNING_TIME)),
+                            }
+                        },
+                        Some(MenuEntry::Editor) => {
+                            editor = Editor::new();
+                            game_state = GameState::Editor;
+                        },
+                        Some(MenuEntry::Settings) => {
+                            settings_menu.selected = 0;
+                            game_state = GameState::Settings;
+                        },
+                        Some(MenuEntry::Mutators) => {
+                            mutators_screen = MutatorsScreen::new();
+                            game_state = GameState::Mutators;
+                        },
+                        Some(MenuEntry::Customize) => {
+                            customize = Customize::new();
+                            game_state = GameState::Customize;
+                        },
+                        Some(MenuEntry::Stats) => game_state = GameState::Stats,
+                        Some(MenuEntry::Leaderboard) => game_state = GameState::Leaderboard,
+                        Some(MenuEntry::Quit) => prompt = Some(Prompt::ExitGame),
+                        None => {},
+                    }
+                },
+                GameState::Settings => {
+                    let selected = settings_menu.selected;
+                    if let Some(index) = settings_menu.handle_input(space.consume()) {
+                        settings.change(index, true);
+                    } else if is_key_pressed(KeyCode::Left) {
+                        settings.change(selected, false);
+                    } else if is_key_pressed(KeyCode::Right) {
+                        settings.change(selected, true);
+                    } else if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Enter) {
+                        settings.save();
+                        lang = Lang::load(&settings.language).await;
+                        game_state = GameState::Menu;
+                    }
+                },
+                GameState::Stats | GameState::Leaderboard => {
+                    if is_key_pressed(KeyCode::Escape) || space.consume() {
+                        game_state = GameState::Menu;
+                    }
+                },
+                GameState::Editor => match editor.update() {
+                    // Test plays are practice runs, so they never touch the leaderboard
+                    Some(EditorAction::TestPlay) => match editor.level.playable_blocks() {
+                        Ok(_) => {
+                            game.board = BoardSource::Editor(editor.level.clone());
+                            game.mode = GameMode::Practice;
+                            reset_game(&mut game, &settings);
+                            game_state = GameState::Game;
+                        },
+                        // Stays in the editor so the layout can be fixed straight away
+                        Err(error) => editor.show_status(error.text_id(), "", None),
+                    },
+                    Some(EditorAction::Back) => game_state = GameState::Menu,
+                    None => {},
+                },
+                // Equipping takes effect straight away, so the next run already uses the skin
+                GameState::Customize => match customize.update(&stats, space.consume()) {
+                    Some(CustomizeAction::Equip(skin)) => {
+                        settings.skin = skin.name.to_string();
+                        settings.save();
+                        game.skin = skin;
+                    },
+                    Some(CustomizeAction::Back) => game_state = GameState::Menu,
+                    None => {},
+                },
+                GameState::Mutators => match mutators_screen.update(&mut settings, space.consume()) {
+                    Some(MutatorsAction::Back) => game_state = GameState::Menu,
+                    None => {},
+                },
+                GameState::LevelSelect => match level_select.update(&campaign, &progress, space.consume()) {
+                    // A broken level file is refused here rather than handed to the game to win instantly
+                    Some(LevelSelectAction::Play(index)) => match campaign[index].playable_blocks() {
+                        Ok(_) => {
+                            game.board = BoardSource::Campaign {
+                                index,
+                                level: campaign[index].clone(),
+                            };
+                            game.mode = GameMode::Campaign;
+                            reset_game(&mut game, &settings);
+                            session_log = start_session_log(&game, &settings);
+                            game_state = GameState::Game;
+                        },
+                        Err(error) => {
+                            level_warning = Some((error, LEVEL_WARNING_TIME));
+                            game_state = GameState::Menu;
+                        },
+                    },
+                    Some(LevelSelectAction::Back) => game_state = GameState::Menu,
+                    None => {},
+                },
+                GameState::Game if is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape) => {
+                    game.restart_hold = 0f32;
+                    game.run_restart_hold = 0f32;
+                    pause_menu.selected = 0;
+                    game_state = GameState::Paused;
+                },
+                GameState::Paused => {
+                    let confirm = space.consume() || is_key_pressed(KeyCode::Enter);
+                    let choice = pause_menu.handle_input(confirm);
+                    if is_key_pressed(KeyCode::P) || is_key_pressed(KeyCode::Escape) || matches!(choice, Some(PauseEntry::Resume)) {
+                        game_state = GameState::Game;
+                    } else if is_key_pressed(KeyCode::Q) || matches!(choice, Some(PauseEntry::Quit)) {
+                        prompt = Some(Prompt::QuitToMenu);
+                    }
+                },
+                GameState::Game => {
+                    let shift_down = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+                    // Shift+R throws the whole run away and starts over from the first serve
+                    if shift_down && is_key_down(KeyCode::R) {
+                        game.restart_hold = 0f32;
+                        game.run_restart_hold += get_frame_time();
+                        if game.run_restart_hold >= RUN_RESTART_HOLD_TIME {
+                            reset_game(&mut game, &settings);
+                            session_log = start_session_log(&game, &settings);
+                        }
+                    // Practice restarts instantly, campaign needs R held so a stray press mid-rally doesn't wipe the level
+                    } else if game.mode == GameMode::Practice && is_key_pressed(KeyCode::R) {
+                        game.restart_level();
+                    } else if game.mode == GameMode::Campaign && is_key_down(KeyCode::R) {
+                        game.restart_hold += get_frame_time();
+                        if game.restart_hold >= RESTART_HOLD_TIME {
+                            game.restart_level();
+                        }
+                    } else {
+                        game.restart_hold = 0f32;
+                        game.run_restart_hold = 0f32;
                     }
-                }
-                for ball in spawn_later.into_iter() {
-                    balls.push(ball);
-                }
 
-                let balls_len = balls.len();
-                // Remove balls that went past the paddle
-                balls.retain(|ball| ball.rect.y < screen_height());
+                    game.update_fast_forward(is_key_down(KeyCode::Down));
+                    // A frozen step is one tick exactly, so hit-stop and fast-forward are left out of it
+                    let frame_time = frame_advance.frame_time(get_frame_time());
+                    let dt = if frame_advance.frozen { frame_time } else { game.scaled_dt(frame_time) };
+                    if dt > 0f32 {
+                        frame_advance.tick += 1;
+                    }
+                    game.elapsed += dt;
+                    game.level_time += dt;
+                    game.update_focus(is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl), dt);
+                    game.update_slow_motion(settings.last_block_slow_motion, dt);
+                    let speed_scale = game.ball_speed_scale();
+                    for ball in game.balls.iter_mut() {
+                        ball.speed_scale = speed_scale;
+                    }
+                    let assist_target = if settings.auto_aim { game.auto_aim_target() } else { None };
+                    game.paddle.update(dt, &settings, assist_target);
+
+                    if game.awaiting_serve {
+                        game.hold_ball_on_paddle();
+                        if dt > 0f32 && space.consume() {
+                            game.launch_ball(&settings);
+                        }
+                    } else {
+                        // Gravity only bends the heading, so the speed stays whatever it would have been
+                        let pull = game.level_rules.gravity.unwrap_or(0f32) * dt;
+                        for ball in game.balls.iter_mut() {
+                            if pull > 0f32 {
+                                ball.vel = (ball.vel + vec2(0f32, pull)).normalize();
+                            }
+                            ball.update(dt, game.field, game.modifiers.wrap_sides);
+                            if let Some(heatmap) = game.heatmap.as_mut() {
+                                heatmap.record(ball.rect.point() + ball.rect.size() * 0.5f32);
+                            }
+                        }
+                    }
+                    if !game.awaiting_serve {
+                        game.watch_for_stuck_balls(dt);
+                    }
+                    game.forgiveness_timer = (game.forgiveness_timer - dt).max(0f32);
+                    game.combo = (game.combo - COMBO_DRAIN * dt).max(0f32);
+                    announcement_cooldown = (announcement_cooldown - dt).max(0f32);
+                    announcement = announcement
+                        .map(|(text_id, timer)| (text_id, timer - dt))
+                        .filter(|(_, timer)| *timer > 0f32);
+
+                    for block in game.blocks.iter_mut() {
+                        block.hit_flash_frames = block.hit_flash_frames.saturating_sub(1);
+                        block.reveal_timer = (block.reveal_timer - dt).max(0f32);
+                    }
+
+                    let mut hits = vec![];
+                    for (ball_index, ball) in game.balls.iter_mut().enumerate() {
+                        // The bounce happens straight away, the damage is applied once every ball has moved
+                        // Tunneling through the paddle is checked first, since the overlap test can't see it
+                        let swept = swept_paddle_collision(ball, &game.paddle);
+                        let surface = if swept.is_some() {
+                            Some(Surface::Paddle)
+                        } else {
+                            closest_surface(ball, Some(&paddle_bounds(&game.paddle.rect, game.paddle.tilt)), &game.blocks)
+                        };
+                        match surface {
+                            Some(Surface::Paddle) => {
+                                let contact = swept.or_else(|| resolve_paddle_contact(&mut ball.rect, &mut ball.vel, &game.paddle.rect, game.paddle.tilt));
+                                if let Some(contact) = contact {
+                                    ball.paddle_cooldown = PADDLE_COOLDOWN;
+                                    sounds.play_impact(SoundEffect::Bounce, &contact);
+                                    game.combo = 0f32;
+                                    // Bounces off the paddle's top take their angle from where the ball landed
+                                    // unless the level turns paddle aim off and keeps the plain reflection. A
+                                    // tilted paddle keeps its own reflection, held to the usual steepest angle
+                                    if contact.side == Side::Top {
+                                        if game.paddle.tilt != 0f32 {
+                                            let max = game.physics.max_bounce_angle;
+                                            let angle = ball.vel.x.atan2(-ball.vel.y).clamp(-max, max);
+                                            ball.vel = vec2(angle.sin(), -angle.cos());
+                                        } else if !game.level_rules.no_paddle_aim {
+                                            let mut angle = paddle_bounce_angle(&ball.rect, &game.paddle.rect, &game.physics);
+                                            if game.paddle.is_dashing() {
+                                                let max = game.physics.max_bounce_angle;
+                                                angle = (angle + game.paddle.dash_direction * game.physics.dash_english).clamp(-max, max);
+                                            }
+                                            ball.vel = vec2(angle.sin(), -angle.cos());
+                                        }
+                                        game.paddle.hit(ball.color);
+                                    }
+                                }
+                            },
+                            Some(Surface::Block(i)) => {
+                                if let Some(contact) = resolve_contact(&mut ball.rect, &mut ball.vel, &game.blocks[i].rect) {
+                                    sounds.play_impact(SoundEffect::Bounce, &contact);
+                                    hits.push(BlockHit {
+                                        block: i,
+                                        ball: ball_index,
+                                        contact: contact.point,
+                                        lethal: false,
+                                    });
+                                }
+                            },
+                            None => {},
+                        }
+
+                        let mut entered_portal = None;
+                        for (i, block) in game.blocks.iter().enumerate() {
+                            // Portals swallow the ball rather than bouncing it
+                            if let BlockType::Portal(pair) = block.block_type {
+                                if ball.portal_cooldown <= 0f32 && ball.rect.overlaps(&block.rect) {
+                                    entered_portal = Some((i, pair));
+                                }
+                                continue;
+                            }
+
+                            // A fireball skips the bounce and chips at every block it passes through
+                            if ball.fireball && ball.rect.overlaps(&block.rect) {
+                                // The closest point on the block to the ball's center is where they touched
+                                let ball_center = ball.rect.point() + ball.rect.size() * 0.5f32;
+                                hits.push(BlockHit {
+                                    block: i,
+                                    ball: ball_index,
+                                    contact: ball_center.clamp(block.rect.point(), block.rect.point() + block.rect.size()),
+                                    lethal: false,
+                                });
+                            }
+                        }
+
+                        if let Some((entry, pair)) = entered_portal {
+                            let exit = game
+                                .blocks
+                                .iter()
+                                .enumerate()
+                                .find(|(i, block)| *i != entry && block.block_type == BlockType::Portal(pair));
+                            if let Some((_, exit)) = exit {
+                                teleport_ball(ball, &exit.rect);
+                            }
+                        }
+                    }
+                    if !hits.is_empty() {
+                        game.cut_fast_forward();
+                    }
+                    game.apply_block_hits(hits);
+
+                    game.update_power_ups(dt);
+                    game.update_debris(dt);
+
+                    for particle in game.particles.iter_mut() {
+                        particle.update(dt);
+                    }
+                    game.particles.retain(|particle| particle.life > 0f32);
+                    game.particles.truncate(game.effects.max_particles);
+
+                    for popup in game.popups.iter_mut() {
+                        popup.update(dt);
+                    }
+                    game.popups.retain(|popup| popup.life > 0f32);
+
+                    // The forgiveness floor turns back the first ball to fall off the bottom, then drops
+                    if game.forgiveness_timer > 0f32 {
+                        if let Some(ball) = game.balls.iter_mut().find(|ball| ball.rect.y >= screen_height() - ball.rect.h) {
+                            ball.rect.y = screen_height() - ball.rect.h;
+                            ball.vel.y = -ball.vel.y.abs();
+                            game.forgiveness_timer = 0f32;
+                        }
+                    }
 
-                //If the last ball went past the paddle the player loses a life
-                let removed_balls = balls_len - balls.len();
-                if removed_balls > 0 && balls.is_empty() {
-                    player_lives -= 1;
-                    balls.push(Ball::new(
-                        paddle.rect.point()
-                            + vec2(paddle.rect.w * 0.5f32 - BALL_SIZE * 0.5f32, -50f32),
-                    ));
+                    let balls_len = game.balls.len();
+                    // Remove balls that went past the paddle, or in danger line mode that dropped
+                    // past the line with the paddle nowhere underneath
+                    let danger_y = game.paddle.rect.y - DANGER_LINE_OFFSET;
+                    let paddle_rect = game.paddle.rect;
+                    let mut last_lost = None;
+                    game.balls.retain(|ball| {
+                        let forfeited = settings.danger_line
+                            && ball.vel.y > 0f32
+                            && ball.rect.bottom() > danger_y
+                            && (ball.rect.right() < paddle_rect.left() || ball.rect.left() > paddle_rect.right());
+                        let kept = ball.rect.y < screen_height() && !forfeited;
+                        if !kept {
+                            last_lost = Some(ball.clone());
+                        }
+                        kept
+                    });
+
+                    //If the last ball went past the paddle the player loses a life
+                    let removed_balls = balls_len - game.balls.len();
+                    let saved_ball = last_lost.filter(|_| game.safety_net_armed && game.balls.is_empty());
+                    if let Some(mut ball) = saved_ball {
+                        // The safety net puts the last ball back on the floor heading up, once per level
+                        ball.rect.y = ball.rect.y.min(screen_height() - ball.rect.h);
+                        ball.vel.y = -ball.vel.y.abs();
+                        game.balls.push(ball);
+                        game.safety_net_armed = false;
+                    } else if removed_balls > 0 && game.balls.is_empty() {
+                        game.player_lives -= 1;
+                        game.level_lives_lost += 1;
+                        game.events.push(GameEvent::LifeLost {
+                            lives_left: game.player_lives,
+                        });
+                        // The replacement waits on the paddle for the player to serve it
+                        let ball = game.new_ball(game.paddle.rect.point());
+                        game.balls.push(ball);
+                        game.awaiting_serve = true;
+                        game.hold_ball_on_paddle();
+
+                        if game.player_lives <= 0 {
+                            game.events.push(GameEvent::GameOver { score: game.final_score() });
+                            game_state = GameState::Dead;
+                            // A press from the last moments of play shouldn't skip the end screen
+                            space.clear();
+                        }
+                    }
+                    // Remove blocks that were destroyed - if lambda is true then it stays, if false it is removed from the vector
+                    game.blocks.retain(|block| block.lives > 0);
 
-                    if player_lives <= 0 {
+                    let cleared = !game.blocks.iter().any(|block| block.is_destructible());
+                    if !cleared && game.time_left() == Some(0f32) && game_state == GameState::Game {
+                        // Running out of time ends the run like losing the last life
+                        game.player_lives = 0;
+                        game.events.push(GameEvent::GameOver { score: game.final_score() });
                         game_state = GameState::Dead;
+                        space.clear();
+                    } else if cleared {
+                        game.events.push(GameEvent::LevelCleared);
+                        // A campaign run with levels to go carries on through the shop, so it isn't over yet
+                        if game.next_campaign_index(campaign.len()).is_none() {
+                            game.events.push(GameEvent::GameOver { score: game.final_score() });
+                        }
+                        game_state = GameState::Won;
+                        space.clear();
                     }
-                }
-                // Remove blocks that were destroyed - if lambda is true then it stays, if false it is removed from the vector
-                blocks.retain(|block| block.lives > 0);
 
-                if blocks.is_empty() {
-                    game_state = GameState::Won;
-                }
-            },
-            GameState::Won | GameState::Dead => {
-                if is_key_pressed(KeyCode::Space) {
-                    game_state = GameState::Menu;
-                    reset_game(
-                        &mut score,
-                        &mut player_lives,
-                        &mut blocks,
-                        &mut balls,
-                        &mut paddle,
-                    );
+                    if dt > 0f32 {
+                        frame_advance.last_events = game.events.iter().map(GameEvent::name).collect();
+                    }
+                    for event in game.events.drain(..) {
+                        if let Some(log) = session_log.as_mut() {
+                            log.record(&event, game.elapsed);
+                        }
+
+                        match event {
+                            GameEvent::BlockDestroyed { block_type, .. } => {
+                                sounds.play(SoundEffect::Break, 1f32);
+                                game.blocks_destroyed += 1;
+                                game.level_blocks_destroyed += 1;
+                                if game.level_blocks_destroyed == game.level_blocks_total {
+                                    game.progress_flash = PROGRESS_FLASH_TIME;
+                                }
+                                if block_type == BlockType::Explosive {
+                                    camera_fx.punch(EXPLOSION_PUNCH);
+                                    if game.effects.animate_background {
+                                        background.explosion();
+                                    }
+                                }
+                            },
+                            GameEvent::LevelCleared if !game.cheated => match game.board {
+                                // Honest clears of a hand-made level count towards campaign progress
+                                BoardSource::Campaign { index, ref level } => {
+                                    let stars = level.stars(game.level_time, game.level_lives_lost);
+                                    game.stars_earned = Some(stars);
+                                    // Only what was scored on this level, not what was carried into it
+                                    progress.record_clear(index, game.score - game.level_start.score, game.level_time, stars);
+                                    progress.save();
+                                },
+                                // Only honest campaign clears of generated boards go on the time attack board
+                                BoardSource::Generated if game.mode == GameMode::Campaign && leaderboard.qualifies(game.elapsed) => {
+                                    initials_entry = Some(InitialsEntry::new(&settings.initials));
+                                },
+                                _ => {},
+                            },
+                            GameEvent::Chain { length, .. } if announcement_cooldown <= 0f32 => {
+                                if let Some((_, text_id)) = ANNOUNCEMENTS.iter().find(|(milestone, _)| *milestone == length) {
+                                    announcement = Some((text_id, ANNOUNCEMENT_TIME));
+                                    announcement_cooldown = ANNOUNCEMENT_COOLDOWN;
+                                }
+                            },
+                            GameEvent::LifeLost { .. } => {
+                                // Controller rumble would go here, but macroquad doesn't expose gamepads yet
+                                camera_fx.shake(LIFE_LOST_SHAKE);
+                                game.life_lost_flash = LIFE_LOST_FLASH_TIME;
+                                game.hit_stop = LIFE_LOST_HIT_STOP;
+                            },
+                            GameEvent::GameOver { score } => {
+                                let run = RunTotals {
+                                    blocks_destroyed: game.blocks_destroyed,
+                                    play_time: game.elapsed,
+                                    score,
+                                };
+                                stats.record_run(run, game.stats_recorded, game.longest_chain, game.cheated);
+                                stats.save();
+                                game.stats_recorded = Some(run);
+                                if let Some(heatmap) = game.heatmap.as_mut() {
+                                    heatmap.save();
+                                }
+
+                                #[cfg(feature = "online")]
+                                if !game.cheated {
+                                    online.submit(online::ScoreSubmission {
+                                        initials: settings.initials.clone(),
+                                        score,
+                                        seed: game.seed,
+                                    });
+                                }
+                            },
+                            _ => {},
+                        }
+                    }
+                },
+                GameState::Dead if is_key_pressed(KeyCode::C) && game.can_continue() => {
+                    game.continue_run();
+                    game_state = GameState::Game;
+                },
+                GameState::Dead if is_key_pressed(KeyCode::R) => {
+                    game.retry_level();
+                    game_state = GameState::Game;
+                },
+                GameState::Won if initials_entry.is_some() => {
+                    if let Some(entry) = initials_entry.as_mut() {
+                        if entry.update() {
+                            leaderboard.insert(LeaderboardEntry {
+                                initials: entry.initials(),
+                                time: game.elapsed,
+                                assisted: game.modifiers.assisted,
+                                continued: game.continues_used > 0,
+                                mutators: game.modifiers.mutators.clone(),
+                            });
+                            leaderboard.save();
+                            // Remembered as the default for next time, and for online scores
+                            settings.initials = entry.initials();
+                            settings.save();
+                            initials_entry = None;
+                            space.clear();
+                        }
+                    }
+                },
+                GameState::Won if game.next_campaign_index(campaign.len()).is_some() => {
+                    if space.consume() {
+                        interlude = Interlude::new();
+                        game_state = GameState::Interlude;
+                    }
+                },
+                GameState::Interlude => match interlude.update(game.score, space.consume()) {
+                    Some(InterludeAction::Continue(item)) => {
+                        if let Some(index) = game.next_campaign_index(campaign.len()) {
+                            game.start_next_level(index, campaign[index].clone(), item);
+                        }
+                        game_state = GameState::Game;
+                    },
+                    Some(InterludeAction::Quit) => prompt = Some(Prompt::QuitToMenu),
+                    None => {},
+                },
+                // Straight into another run on a generated board, with the same layout or a fresh one
+                GameState::Won | GameState::Dead
+                    if matches!(game.board, BoardSource::Generated) && (is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::N)) =>
+                {
+                    if is_key_pressed(KeyCode::S) {
+                        game.replay_seed = Some(game.seed);
+                    }
+                    reset_game(&mut game, &settings);
+                    session_log = start_session_log(&game, &settings);
+                    game_state = GameState::Game;
+                },
+                GameState::Won | GameState::Dead => {
+                    if space.consume() {
+                        session_log = None;
+                        game_state = game.leave_board();
+                        reset_game(&mut game, &settings);
+                    }
                 }
             }
         }
 
+        #[cfg(feature = "online")]
+        online.poll();
+
+        // Buffered log lines only hit the disk between states, never mid-rally
+        if game_state != last_state {
+            if let Some(log) = session_log.as_mut() {
+                log.flush();
+            }
+            last_state = game_state;
+            state_time = 0f32;
+        }
+        state_time += get_frame_time();
+        game.update_displayed_score(get_frame_time());
+        let end_text_scale = ease_out_back(state_time / END_TEXT_ANIMATION_TIME);
+
         clear_background(DARKGRAY);
-        paddle.draw();
+        let wanted_theme = Theme::for_level(game.campaign_index());
+        if wanted_theme != theme {
+            theme = wanted_theme;
+            background = theme.create();
+        }
+        if game.effects.animate_background && game_state != GameState::Paused {
+            background.update(get_frame_time());
+        }
+        background.draw();
+        camera_fx.update(get_frame_time());
+        game.update_level_progress(get_frame_time());
+        music.update(&game, game_state, settings.music, get_frame_time());
 
-        for block in blocks.iter() {
-            block.draw();
+        // The board isn't shown until loading has finished
+        // Everything in the playfield goes through the world camera so shake and zoom apply to all of it
+        if !matches!(
+            game_state,
+            GameState::Loading | GameState::Editor | GameState::LevelSelect | GameState::Customize | GameState::Interlude
+        ) {
+            camera_fx.apply(&game.effects);
+            draw_world(&game, &settings, game_state, debug_overlay, &lang, font);
+            // Overlays and HUD stay fixed in screen space
+            set_default_camera();
         }
 
-        for ball in balls.iter() {
-            ball.draw();
+        // A red border around the screen edges that fades out, or a thin and fainter outline without flashes
+        if game.life_lost_flash > 0f32 {
+            let fade = game.life_lost_flash / LIFE_LOST_FLASH_TIME;
+            let (width, alpha) = if game.effects.flashes {
+                (LIFE_LOST_FLASH_WIDTH * 2f32, 0.6f32 * fade)
+            } else {
+                (6f32, 0.35f32 * fade)
+            };
+            draw_rectangle_lines(0f32, 0f32, screen_width(), screen_height(), width, Color::new(1f32, 0f32, 0f32, alpha));
+            game.life_lost_flash -= get_frame_time();
         }
 
+        let cheat_note = if game.cheated { lang.tr("game.cheated") } else { "" };
         match game_state {
+            GameState::Loading => {
+                draw_loading_screen(&loader, &lang, font);
+            },
             GameState::Menu => {
-                draw_title_text("Press SPACE to start", font);
+                draw_centered_text(lang.tr("title"), 120f32, font);
+                let labels: Vec<String> = main_menu.entries.iter().map(|entry| lang.tr(entry.text_id()).to_string()).collect();
+                main_menu.draw(&labels, 200f32, 45f32, font);
+                draw_centered_text(lang.tr("menu.help"), screen_height() - 60f32, font);
+                #[cfg(feature = "online")]
+                draw_online_scores(&online, &lang, font);
+            },
+            GameState::Settings => {
+                draw_settings(&settings, &settings_menu, &lang, font);
+            },
+            GameState::Stats => {
+                draw_stats(&stats, &lang, font);
             },
             GameState::Game => {
-                let score_text = format!("Score: {}", score);
-                let score_text_dim = measure_text(&score_text, Some(font), 30u16, 1.0);
-
-                // Displays the score at the top of the screen
-                draw_text_ex(
-                    &score_text,
-                    screen_width() * 0.5f32 - score_text_dim.width * 0.5f32,
-                    40.0,
-                    TextParams {
-                        font,
-                        font_size: 30u16,
-                        color: WHITE,
-                        ..Default::default()
-                    },
-                );
-
-                // Displays the player's remaining lives at the top of the screen
-                draw_text_ex(
-                    &format!("Lives: {}", player_lives),
-                    30.0,
-                    40.0,
-                    TextParams {
-                        font,
-                        font_size: 30u16,
-                        color: WHITE,
-                        ..Default::default()
-                    },
-                );
+                draw_hud(&game, &lang, font);
+                if game.awaiting_serve {
+                    draw_centered_text(lang.tr("game.serve"), screen_height() * 0.5f32 + 50f32, font);
+                }
+                // Starts a little large and shrinks back as it fades
+                if let Some((text_id, timer)) = announcement {
+                    let t = timer / ANNOUNCEMENT_TIME;
+                    draw_title_text_ex(lang.tr(text_id), font, 1f32 + 0.3f32 * t, Color::new(1f32, 0.84f32, 0f32, t));
+                }
+            },
+            GameState::Paused => {
+                // Dim the frozen board rather than hiding it, then put the HUD back on top so it stays readable
+                draw_rectangle(0f32, 0f32, screen_width(), screen_height(), Color::new(0f32, 0f32, 0f32, 0.6f32));
+                draw_hud(&game, &lang, font);
+                draw_title_text(lang.tr("game.paused"), font);
+                let labels: Vec<String> = pause_menu.entries.iter().map(|entry| lang.tr(entry.text_id()).to_string()).collect();
+                pause_menu.draw(&labels, screen_height() * 0.5f32 + 70f32, 50f32, font);
+            },
+            GameState::Leaderboard => {
+                draw_leaderboard(&leaderboard, &lang, font);
+            },
+            GameState::Editor => {
+                editor.draw(&lang, font);
+            },
+            GameState::LevelSelect => {
+                level_select.draw(&campaign, &progress, &lang, font);
+            },
+            GameState::Customize => {
+                customize.draw(&settings.skin, &stats, &lang, font);
+            },
+            GameState::Mutators => {
+                mutators_screen.draw(&settings, &lang, font);
+            },
+            GameState::Interlude => {
+                interlude.draw(game.score, &lang, font);
             },
             GameState::Won => {
-                draw_title_text(&format!("You won with a score of {}! ", score), font);
+                draw_heatmap_overlay(&game, debug_overlay);
+                let text = lang.tr("game.won").replace("{score}", &game.final_score().to_string());
+                draw_title_text_scaled(&format!("{}{}", text, cheat_note), font, end_text_scale);
+                draw_level_progress(&game);
+                if let Some(stars) = game.stars_earned {
+                    draw_star_rating(stars, state_time - END_TEXT_ANIMATION_TIME);
+                }
+                if let Some(entry) = initials_entry.as_ref() {
+                    draw_initials_entry(entry, game.elapsed, &lang, font);
+                }
+                if game.next_campaign_index(campaign.len()).is_some() {
+                    draw_centered_text(lang.tr("game.next_level"), screen_height() * 0.5f32 + 130f32, font);
+                }
+                if initials_entry.is_none() {
+                    draw_replay_hint(&game, &lang, font);
+                }
             },
             GameState::Dead => {
-                draw_title_text(&format!("You lost with a score of {}!", score), font);
+                draw_heatmap_overlay(&game, debug_overlay);
+                let text = lang.tr("game.lost").replace("{score}", &game.final_score().to_string());
+                draw_title_text_scaled(&format!("{}{}{}", text, cheat_note, lang.tr("game.retry")), font, end_text_scale);
+                if game.can_continue() {
+                    let left = (MAX_CONTINUES - game.continues_used).to_string();
+                    draw_centered_text(&lang.tr("game.continue").replace("{n}", &left), screen_height() * 0.5f32 + 60f32, font);
+                }
+                draw_replay_hint(&game, &lang, font);
+                draw_centered_text(lang.tr("game.leave"), screen_height() * 0.5f32 + 140f32, font);
+            }
+        }
+
+        if (debug_overlay || show_ball_speed) && matches!(game_state, GameState::Game | GameState::Paused) {
+            draw_ball_speed(&game, font);
+        }
+
+        match prompt {
+            Some(Prompt::QuitToMenu) => draw_prompt(lang.tr("prompt.quit"), font),
+            Some(Prompt::ExitGame) => draw_prompt(lang.tr("prompt.exit"), font),
+            None => {},
+        }
+
+        if font_warning > 0f32 {
+            draw_warning_banner(&lang.tr("warning.font").replace("{path}", assets::FONT_PATH), screen_height());
+            font_warning -= get_frame_time();
+        }
+
+        // Stacked above the font warning in case both are up
+        if let Some((error, timer)) = level_warning.as_mut() {
+            draw_warning_banner(lang.tr(error.text_id()), screen_height() - 40f32);
+            *timer -= get_frame_time();
+            if *timer <= 0f32 {
+                level_warning = None;
+            }
+        }
+
+        if debug_overlay {
+            let text = format!(
+                "balls: {}  substeps: {}  blocks: {}  particles: {}  seed: {}  unstuck: {}",
+                game.balls.len(),
+                game.balls.iter().map(|ball| ball.substeps).max().unwrap_or(0),
+                game.blocks.len(),
+                game.particles.len(),
+                game.seed,
+                game.balls_unstuck,
+            );
+            draw_text(&text, 10f32, screen_height() - 15f32, 24f32, WHITE);
+            if matches!(game_state, GameState::Game | GameState::Paused) {
+                tuning_panel.draw(&game.physics);
             }
         }
 
+        if game_state == GameState::Game {
+            frame_advance.draw(&game);
+        }
+
+        if console.show_fps {
+            draw_text(&format!("{} fps", get_fps()), screen_width() - 90f32, screen_height() - 15f32, 24f32, WHITE);
+        }
+        console.draw(font);
+
         next_frame().await;
+
+        // Sleeps off whatever is left of the frame budget. Physics runs on get_frame_time() so this
+        // only trades power draw for smoothness, it doesn't change game speed
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(frame_time) = settings.fps_cap.frame_time() {
+            let elapsed = get_time() - frame_start;
+            if elapsed < frame_time {
+                std::thread::sleep(std::time::Duration::from_secs_f64(frame_time - elapsed));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Space left of the first column and right of the last, which should match for a centered board
+    fn margins(layout: &BoardLayout, columns: usize, area: Rect) -> (f32, f32) {
+        let right_edge = layout.origin.x + layout.cell_size.x * (columns - 1) as f32 + layout.block_size.x;
+        (layout.origin.x - area.x, area.right() - right_edge)
+    }
+
+    #[test]
+    fn board_is_centered_without_padding() {
+        let area = Rect::new(100f32, 50f32, 1000f32, 600f32);
+        let layout = BoardLayout::fit_in(6, 5, area, 0f32);
+        assert_eq!(layout.cell_size, BLOCK_SIZE);
+        let (left, right) = margins(&layout, 6, area);
+        assert!((left - right).abs() < 0.01f32 && left > 0f32, "{} {}", left, right);
+    }
+
+    #[test]
+    fn board_is_scaled_and_centered_with_wide_padding() {
+        let area = Rect::new(100f32, 50f32, 1000f32, 600f32);
+        let layout = BoardLayout::fit_in(6, 5, area, 200f32);
+        assert!(layout.block_size.x > 0f32 && layout.block_size.x < BLOCK_SIZE.x);
+        let (left, right) = margins(&layout, 6, area);
+        assert!((left - right).abs() < 0.01f32 && left >= 0f32, "{} {}", left, right);
+    }
+
+    #[test]
+    fn padding_outside_the_allowed_range_is_unreadable() {
+        for padding in ["inf", "1e30", "NaN", "-1", "201"] {
+            let text = format!("version 3\npadding {}\nRR\n", padding);
+            assert!(matches!(Level::parse(&text), Err(LevelError::Unreadable)), "padding {}", padding);
+        }
+        assert!(Level::parse("version 3\npadding 200\nRR\n").is_ok());
+    }
+
+    #[test]
+    fn unbounded_gravity_and_time_limits_are_unreadable() {
+        for value in ["inf", "1e39", "NaN", "-inf"] {
+            for setting in ["gravity", "timed"] {
+                let text = format!("version 3\n{} {}\nRR\n", setting, value);
+                assert!(matches!(Level::parse(&text), Err(LevelError::Unreadable)), "{} {}", setting, value);
+            }
+        }
+        assert!(matches!(Level::parse("version 3\ngravity 21\nRR\n"), Err(LevelError::Unreadable)));
+        assert!(matches!(Level::parse("version 3\ntimed 3601\nRR\n"), Err(LevelError::Unreadable)));
+        let level = Level::parse("version 3\ngravity 20\ntimed 3600\nRR\n").ok().unwrap();
+        assert!(level.modifiers.gravity == Some(20f32) && level.modifiers.timed == Some(3600f32));
+    }
+
+    // A 3 by 2 board at full size, starting at the top left of a wide area
+    fn test_board() -> Vec<Block> {
+        let layout = BoardLayout::fit_in(3, 2, Rect::new(0f32, 0f32, 2000f32, 2000f32), BLOCK_PADDING);
+        (0..6).map(|i| layout.block(i % 3, i / 3, BlockType::Regular)).collect()
+    }
+
+    #[test]
+    fn big_ball_hits_are_applied_in_reading_order() {
+        let blocks = test_board();
+        // Over the corner shared by the first two blocks of both rows
+        let corner = blocks[4].rect.point();
+        let ball = Rect::new(corner.x - 40f32, corner.y - 40f32, BIG_BALL_SIZE, BIG_BALL_SIZE);
+        let mut hits: Vec<BlockHit> = blocks
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, block)| block.rect.overlaps(&ball))
+            .map(|(i, block)| BlockHit {
+                block: i,
+                ball: 0,
+                contact: block.rect.point(),
+                lethal: false,
+            })
+            .collect();
+        assert_eq!(hits.len(), 4);
+        sort_hits(&mut hits, &blocks);
+        assert_eq!(hits.iter().map(|hit| hit.block).collect::<Vec<_>>(), vec![0, 1, 3, 4]);
+    }
+
+    #[test]
+    fn hits_on_one_block_are_ordered_by_ball() {
+        let blocks = test_board();
+        let hit = |ball| BlockHit {
+            block: 2,
+            ball,
+            contact: Vec2::ZERO,
+            lethal: false,
+        };
+        let mut hits = vec![hit(2), hit(0), hit(1)];
+        sort_hits(&mut hits, &blocks);
+        assert_eq!(hits.iter().map(|hit| hit.ball).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    // A 50px ball pushed 10px into a 100px square at the origin from the given direction
+    fn approach(offset: Vec2, vel: Vec2) -> (Rect, Vec2, Contact) {
+        let target = Rect::new(0f32, 0f32, 100f32, 100f32);
+        let mut ball = Rect::new(25f32 + offset.x, 25f32 + offset.y, 50f32, 50f32);
+        let mut vel = vel;
+        let contact = resolve_contact(&mut ball, &mut vel, &target).expect("overlapping");
+        (ball, vel, contact)
+    }
+
+    #[test]
+    fn contact_side_matches_the_approach() {
+        let (ball, vel, contact) = approach(vec2(0f32, -65f32), vec2(0f32, 1f32));
+        assert_eq!(contact.side, Side::Top);
+        assert_eq!((ball.bottom(), vel), (0f32, vec2(0f32, -1f32)));
+
+        let (ball, vel, contact) = approach(vec2(0f32, 65f32), vec2(0f32, -1f32));
+        assert_eq!(contact.side, Side::Bottom);
+        assert_eq!((ball.top(), vel), (100f32, vec2(0f32, 1f32)));
+
+        let (ball, vel, contact) = approach(vec2(-65f32, 0f32), vec2(1f32, 0f32));
+        assert_eq!(contact.side, Side::Left);
+        assert_eq!((ball.right(), vel), (0f32, vec2(-1f32, 0f32)));
+
+        let (ball, vel, contact) = approach(vec2(65f32, 0f32), vec2(-1f32, 0f32));
+        assert_eq!(contact.side, Side::Right);
+        assert_eq!((ball.left(), vel), (100f32, vec2(1f32, 0f32)));
+    }
+
+    #[test]
+    fn contact_records_velocity_and_point() {
+        let (_, _, contact) = approach(vec2(20f32, -65f32), vec2(0.6f32, 0.8f32));
+        assert_eq!(contact.vel_before, vec2(0.6f32, 0.8f32));
+        assert_eq!(contact.vel_after, vec2(0.6f32, -0.8f32));
+        assert_eq!(contact.point, vec2(70f32, 0f32));
+    }
+
+    #[test]
+    fn separate_rects_have_no_contact() {
+        let mut ball = Rect::new(200f32, 200f32, 50f32, 50f32);
+        let mut vel = vec2(0f32, 1f32);
+        assert!(resolve_contact(&mut ball, &mut vel, &Rect::new(0f32, 0f32, 100f32, 100f32)).is_none());
+        assert_eq!(vel, vec2(0f32, 1f32));
+    }
+
+    #[test]
+    fn fast_ball_on_slow_frames_stays_in_the_field() {
+        let field = Field { left: 100f32, right: 900f32 };
+        let mut ball = Ball::new(vec2(500f32, 300f32), WHITE, 4f32);
+        ball.vel = vec2(0.9f32, -0.45f32).normalize();
+        for _ in 0..200 {
+            ball.update(1f32 / 20f32, field, false);
+            assert!(ball.substeps > 1);
+            assert!(ball.rect.left() >= field.left && ball.rect.right() <= field.right, "{:?}", ball.rect);
+            assert!(ball.rect.top() >= 0f32, "{:?}", ball.rect);
+            // Sent back up whenever it gets low, so it keeps bouncing between the walls and the ceiling
+            if ball.rect.y > 600f32 {
+                ball.vel.y = -ball.vel.y.abs();
+            }
+        }
+    }
+
+    fn full_board() -> Vec<Block> {
+        let layout = BoardLayout::fit_in(6, 5, Rect::new(0f32, 0f32, 2000f32, 2000f32), BLOCK_PADDING);
+        (0..30).map(|i| layout.block(i % 6, i / 6, BlockType::Regular)).collect()
+    }
+
+    fn count_type(blocks: &[Block], block_type: BlockType) -> usize {
+        blocks.iter().filter(|block| block.block_type == block_type).count()
+    }
+
+    #[test]
+    fn block_type_limits_hold_for_every_seed() {
+        for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+            let settings = Settings { difficulty, ..Settings::default() };
+            let distribution = BlockDistribution::for_settings(&settings);
+            for seed in 0..500 {
+                rand::srand(seed);
+                let mut blocks = full_board();
+                assign_block_types(&mut blocks, &distribution);
+                assert!(count_type(&blocks, BlockType::Indestructible) <= distribution.max_indestructible);
+                assert!(count_type(&blocks, BlockType::Trap) <= distribution.max_traps);
+            }
+        }
+    }
+
+    #[test]
+    fn limits_hold_when_every_roll_is_capped() {
+        // Only indestructible blocks and traps can be drawn, so every block past the limits must fall back
+        let distribution = BlockDistribution {
+            weights: [
+                (BlockType::Regular, 0),
+                (BlockType::SpawnBallOnDeath, 0),
+                (BlockType::Explosive, 0),
+                (BlockType::Indestructible, 50),
+                (BlockType::Trap, 50),
+            ],
+            max_indestructible: 3,
+            max_traps: 2,
+        };
+        for seed in 0..100 {
+            rand::srand(seed);
+            let mut blocks = full_board();
+            assign_block_types(&mut blocks, &distribution);
+            assert!(count_type(&blocks, BlockType::Indestructible) <= 3);
+            assert!(count_type(&blocks, BlockType::Trap) <= 2);
+            assert_eq!(blocks.len(), 30);
+        }
+    }
+
+    #[test]
+    fn grazing_paddle_hit_bounces_once() {
+        let paddle = Rect::new(100f32, 500f32, 150f32, 20f32);
+        // Skimming the paddle's top almost side on, just sinking into it
+        let mut ball = Ball::new(vec2(160f32, 500f32 - BALL_SIZE + 0.5f32), WHITE, 1f32);
+        ball.vel = vec2(1f32, 0.02f32).normalize();
+        assert!(matches!(closest_surface(&ball, Some(&paddle), &[]), Some(Surface::Paddle)));
+
+        let contact = resolve_paddle_contact(&mut ball.rect, &mut ball.vel, &paddle, 0f32).unwrap();
+        assert_eq!(contact.side, Side::Top);
+        assert!(ball.vel.y < 0f32);
+        ball.paddle_cooldown = PADDLE_COOLDOWN;
+
+        // Still touching the paddle while it cools down must not count as a second hit
+        ball.rect.y += 0.5f32;
+        assert!(closest_surface(&ball, Some(&paddle), &[]).is_none());
+        ball.paddle_cooldown = 0f32;
+        assert!(matches!(closest_surface(&ball, Some(&paddle), &[]), Some(Surface::Paddle)));
+    }
+
+    #[test]
+    fn chain_bonus_doubles_per_block() {
+        assert_eq!(chain_bonus(2), CHAIN_BONUS);
+        assert_eq!(chain_bonus(3), CHAIN_BONUS * 2);
+        assert_eq!(chain_bonus(4), CHAIN_BONUS * 4);
+        assert_eq!(chain_bonus(6), CHAIN_BONUS * 16);
+        // Long chains stop growing instead of overflowing
+        assert_eq!(chain_bonus(18), CHAIN_BONUS << 16);
+        assert_eq!(chain_bonus(1000), CHAIN_BONUS << 16);
+    }
+
+    #[test]
+    fn saved_layout_loads_where_the_editor_drew_it() {
+        // The editor's grid size, with a few blocks scattered over it
+        let mut level = Level::empty(10, 8);
+        level.set_cell(0, 0, Some(BlockType::Regular));
+        level.set_cell(9, 0, Some(BlockType::Explosive));
+        level.set_cell(4, 3, Some(BlockType::Indestructible));
+        level.set_cell(9, 7, Some(BlockType::Trap));
+        level.set_worn(0, 0, true);
+
+        let loaded = Level::parse(&level.to_text()).ok().unwrap();
+        assert_eq!((loaded.columns, loaded.rows()), (10, 8));
+        assert!(loaded.cells == level.cells && loaded.worn == level.worn);
+
+        let field = Field { left: 0f32, right: 1280f32 };
+        let layout = BoardLayout::fit_field(10, field, BLOCK_PADDING);
+        let blocks = loaded.blocks_in_field(field);
+        assert_eq!(blocks.len(), 4);
+        for block in blocks.iter() {
+            let (column, row) = block.cell;
+            assert!(Some(block.block_type) == level.cell(column, row));
+            assert_eq!(block.rect, layout.block(column, row, block.block_type).rect);
+        }
+        assert_eq!(blocks[0].lives, 1);
+    }
+
+    fn board_value(blocks: &[Block]) -> i32 {
+        blocks
+            .iter()
+            .map(|block| match block.block_type {
+                BlockType::Trap => -TRAP_PENALTY,
+                _ if block.is_destructible() => BLOCK_POINTS,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn boards_are_always_worth_clearing() {
+        for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+            let settings = Settings { difficulty, ..Settings::default() };
+            let distribution = BlockDistribution::for_settings(&settings);
+            for seed in 0..500 {
+                rand::srand(seed);
+                let mut blocks = full_board();
+                assign_block_types(&mut blocks, &distribution);
+                assert!(board_value(&blocks) > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn traps_past_the_break_even_point_are_turned_regular() {
+        // A limit no real difficulty allows, so only the value check can stop the traps
+        let distribution = BlockDistribution {
+            weights: [
+                (BlockType::Regular, 0),
+                (BlockType::SpawnBallOnDeath, 0),
+                (BlockType::Explosive, 0),
+                (BlockType::Indestructible, 0),
+                (BlockType::Trap, 100),
+            ],
+            max_indestructible: 0,
+            max_traps: 30,
+        };
+        rand::srand(1);
+        let mut blocks = full_board();
+        assign_block_types(&mut blocks, &distribution);
+        assert!(board_value(&blocks) > 0);
+        assert!(count_type(&blocks, BlockType::Trap) > 0);
+    }
+
+    #[test]
+    fn squeezed_ball_takes_the_deeper_surface() {
+        let paddle = Rect::new(100f32, 500f32, 150f32, 20f32);
+        let blocks = [Block::new(vec2(100f32, 500f32 - BLOCK_SIZE.y - BALL_SIZE + 4f32), BLOCK_SIZE, BlockType::Regular)];
+        let mut ball = Ball::new(vec2(150f32, 0f32), WHITE, 1f32);
+
+        // Sunk 3 pixels into the paddle and 1 into the block above it
+        ball.rect.y = paddle.y - BALL_SIZE + 3f32;
+        assert!(ball.rect.overlaps(&blocks[0].rect));
+        assert!(matches!(closest_surface(&ball, Some(&paddle), &blocks), Some(Surface::Paddle)));
+
+        // And the other way round
+        ball.rect.y = paddle.y - BALL_SIZE + 1f32;
+        assert!(ball.rect.overlaps(&paddle));
+        assert!(matches!(closest_surface(&ball, Some(&paddle), &blocks), Some(Surface::Block(0))));
+    }
+
+    #[test]
+    fn holes_keep_blocks_on_their_grid_cells() {
+        // A gap inside a row, a blank row, and a short last row
+        let level = Level::parse("R.R\n\n.E\n").ok().unwrap();
+        assert_eq!((level.columns, level.rows()), (3, 3));
+
+        let area = Rect::new(0f32, 0f32, 2000f32, 2000f32);
+        let layout = BoardLayout::fit_in(3, 3, area, BLOCK_PADDING);
+        let blocks = level.blocks_in(area);
+        let cells: Vec<(usize, usize)> = blocks.iter().map(|block| block.cell).collect();
+        assert_eq!(cells, [(0, 0), (2, 0), (1, 2)]);
+        for block in blocks.iter() {
+            assert_eq!(block.rect, layout.block(block.cell.0, block.cell.1, block.block_type).rect);
+        }
+        assert!(blocks[2].block_type == BlockType::Explosive);
+    }
+
+    #[test]
+    fn unwinnable_levels_are_not_playable() {
+        let field = Field { left: 0f32, right: 1280f32 };
+        let empty = Level::parse("...\n...\n").ok().unwrap();
+        assert!(matches!(empty.playable_blocks_in_field(field), Err(LevelError::Empty)));
+        let walls = Level::parse("III\n.I.\n").ok().unwrap();
+        assert!(matches!(walls.playable_blocks_in_field(field), Err(LevelError::NoDestructibleBlocks)));
+        let one_block = Level::parse("I.R\n").ok().unwrap();
+        assert_eq!(one_block.playable_blocks_in_field(field).ok().map(|blocks| blocks.len()), Some(2));
+    }
+
+    // Plays the ball forward the way a frame of the game does, at the game's own step, until its bottom
+    // edge reaches `y`, returning its center and how long that took
+    fn play_until(mut ball: Ball, blocks: &[Block], y: f32, field: Field, gravity: Option<f32>) -> (f32, f32) {
+        let dt = 1f32 / 60f32;
+        let pull = gravity.unwrap_or(0f32) * dt;
+        let mut time = 0f32;
+        while ball.rect.bottom() < y {
+            assert!(time < PREDICTION_TIME);
+            if pull > 0f32 {
+                ball.vel = (ball.vel + vec2(0f32, pull)).normalize();
+            }
+            ball.update(dt, field, false);
+            time += dt;
+            if let Some(Surface::Block(i)) = closest_surface(&ball, None, blocks) {
+                resolve_contact(&mut ball.rect, &mut ball.vel, &blocks[i].rect);
+            }
+        }
+        (ball.rect.x + ball.rect.w * 0.5f32, time)
+    }
+
+    #[test]
+    fn predictions_match_the_played_out_ball() {
+        let field = Field { left: 0f32, right: 600f32 };
+        let blocks = [Block::new(vec2(250f32, 20f32), BLOCK_SIZE, BlockType::Regular)];
+        let mut ball = Ball::new(vec2(100f32, 150f32), WHITE, 1f32);
+        // Off the block and the top wall before coming down
+        ball.vel = vec2(0.6f32, -0.8f32);
+        for gravity in [None, Some(0.8f32)] {
+            let predicted = predict_crossing(&ball, &blocks, 450f32, field, false, gravity).unwrap();
+            let (x, time) = play_until(ball.clone(), &blocks, 450f32, field, gravity);
+            assert!((predicted.x - x).abs() < BALL_SIZE * 0.25f32);
+            assert!((predicted.time - time).abs() < 2f32 / 60f32);
+        }
+    }
+
+    #[test]
+    fn fast_ball_is_caught_crossing_a_thin_paddle() {
+        let paddle = Paddle::at(Rect::new(100f32, 500f32, 150f32, 20f32));
+        // Fast enough to move from above the paddle to below it in one frame
+        let mut ball = Ball::new(vec2(150f32, 440f32), WHITE, 4f32);
+        ball.vel = vec2(0.2f32, 1f32).normalize();
+        ball.update(1f32 / 20f32, Field { left: 0f32, right: 1280f32 }, false);
+        assert!(ball.rect.y > paddle.rect.bottom());
+        assert!(!ball.rect.overlaps(&paddle.rect));
+
+        let contact = swept_paddle_collision(&mut ball, &paddle).unwrap();
+        assert_eq!(contact.side, Side::Top);
+        assert!(ball.vel.y < 0f32);
+        // Put back resting on the paddle's top where it crossed
+        assert!((ball.rect.bottom() - paddle.rect.y).abs() < 0.01f32);
+        assert!(ball.rect.x > 150f32 && ball.rect.right() < paddle.rect.right() + BALL_SIZE);
+    }
+
+    #[test]
+    fn swept_check_ignores_balls_that_never_cross() {
+        let paddle = Paddle::at(Rect::new(100f32, 500f32, 150f32, 20f32));
+        let field = Field { left: 0f32, right: 1280f32 };
+        // Passing the paddle's height well off to its side
+        let mut beside = Ball::new(vec2(400f32, 400f32), WHITE, 4f32);
+        beside.vel = vec2(0f32, 1f32);
+        beside.update(1f32 / 20f32, field, false);
+        assert!(swept_paddle_collision(&mut beside, &paddle).is_none());
+        // Rising back up through it
+        let mut rising = Ball::new(vec2(150f32, 560f32), WHITE, 4f32);
+        rising.vel = vec2(0f32, -1f32);
+        rising.update(1f32 / 20f32, field, false);
+        assert!(swept_paddle_collision(&mut rising, &paddle).is_none());
+    }
+
+    #[test]
+    fn saved_levels_read_back_unchanged() {
+        let mut level = Level::parse("RSE\n.I.\nT12\n").ok().unwrap();
+        level.set_worn(0, 0, true);
+        level.par_time = Some(30f32);
+        level.max_lives_lost = 1;
+        level.padding = 12f32;
+        level.modifiers = LevelModifiers {
+            no_paddle_aim: true,
+            darkness: true,
+            timed: Some(45f32),
+            gravity: Some(0.8f32),
+            max_balls: Some(2),
+        };
+
+        let text = level.to_text();
+        let loaded = Level::parse(&text).ok().unwrap();
+        assert!(loaded.cells == level.cells && loaded.worn == level.worn);
+        assert!(loaded.modifiers == level.modifiers);
+        assert_eq!((loaded.par_time, loaded.max_lives_lost, loaded.padding), (Some(30f32), 1, 12f32));
+        assert_eq!(loaded.to_text(), text);
+    }
+
+    #[test]
+    fn level_files_from_other_versions() {
+        assert!(matches!(Level::parse("version 99\nRRR\n"), Err(LevelError::NewerVersion)));
+        assert!(matches!(Level::parse("version 0\nRRR\n"), Err(LevelError::Unreadable)));
+        // Campaign files predate versioning, so they're read as version 1 and converted up
+        let old = include_str!("../res/levels/01.lvl");
+        assert!(!old.contains("version"));
+        let level = Level::parse(old).ok().unwrap();
+        assert_eq!(level.par_time, Some(45f32));
+        assert!(level.cells.iter().any(|cell| cell.is_some()));
+    }
+
+    #[test]
+    fn archived_version_1_sample_loads() {
+        let level = Level::parse(include_str!("../tests/levels/v1.lvl")).ok().unwrap();
+        assert_eq!((level.columns, level.rows()), (5, 4));
+        assert!(level.cell(2, 0) == Some(BlockType::SpawnBallOnDeath) && level.cell(1, 1).is_none());
+        assert!(level.cell(2, 2) == Some(BlockType::Portal(1)) && level.cell(2, 3) == Some(BlockType::Portal(1)));
+        assert!(!level.worn.contains(&true));
+        assert_eq!((level.par_time, level.max_lives_lost, level.padding), (Some(40f32), 1, BLOCK_PADDING));
+        assert!(level.modifiers.gravity == Some(0.5f32));
+    }
+
+    #[test]
+    fn archived_version_2_sample_loads() {
+        let level = Level::parse(include_str!("../tests/levels/v2.lvl")).ok().unwrap();
+        assert_eq!((level.columns, level.rows()), (5, 4));
+        let worn: Vec<usize> = (0..level.worn.len()).filter(|&i| level.worn[i]).collect();
+        assert_eq!(worn, [0, 4, 6, 8]);
+        assert!(level.cell(0, 0) == Some(BlockType::Regular) && level.cell(3, 2) == Some(BlockType::Indestructible));
+        assert_eq!((level.par_time, level.padding), (Some(30f32), 12f32));
+        assert!(level.modifiers.timed == Some(60f32));
+        // Saved again it's written in the current version, and reads back the same
+        let text = level.to_text();
+        assert!(text.starts_with("version 3\n"));
+        let resaved = Level::parse(&text).ok().unwrap();
+        assert!(resaved.cells == level.cells && resaved.worn == level.worn);
+    }
+
+    #[test]
+    fn cells_newer_than_the_files_version_are_unreadable() {
+        // Worn cells came in version 2 and brittle ones in version 3
+        for text in ["RWR\n", "version 1\nRWR\n", "RBR\n", "version 1\nRBR\n", "version 2\nRBR\n"] {
+            assert!(matches!(Level::parse(text), Err(LevelError::Unreadable)), "{:?}", text);
+        }
+        assert!(Level::parse("version 2\nRWR\n").is_ok());
+        assert!(Level::parse("version 3\nRWB\n").is_ok());
+    }
+
+    #[test]
+    fn paddle_sliding_into_a_falling_ball_bounces_it_up() {
+        let paddle = Rect::new(100f32, 500f32, 150f32, 20f32);
+        // Falling just past the paddle's right end, its center still above the top edge, as the paddle
+        // slides under it. The rects overlap less across than down, so the plain test would push it sideways
+        let mut ball = Rect::new(paddle.right() - 10f32, paddle.y - BALL_SIZE * 0.5f32 - 5f32, BALL_SIZE, BALL_SIZE);
+        let mut vel = vec2(0f32, 1f32);
+        let contact = resolve_paddle_contact(&mut ball, &mut vel, &paddle, 0f32).unwrap();
+        assert_eq!(contact.side, Side::Top);
+        assert!(vel.y < 0f32);
+        assert!(ball.bottom() <= paddle.y + 0.01f32);
+    }
+
+    #[test]
+    fn rising_balls_are_never_knocked_down() {
+        let paddle = Rect::new(100f32, 500f32, 150f32, 20f32);
+        for tilt in [-0.3f32, 0f32, 0.3f32] {
+            for x in [60f32, 150f32, 230f32] {
+                // Its center below the paddle's middle, heading up after a scoop or the safety net
+                let mut ball = Rect::new(x, paddle.y + paddle.h * 0.5f32 - BALL_SIZE * 0.5f32 + 5f32, BALL_SIZE, BALL_SIZE);
+                let mut vel = vec2(0.3f32, -1f32).normalize();
+                if let Some(contact) = resolve_paddle_contact(&mut ball, &mut vel, &paddle, tilt) {
+                    assert!(contact.vel_after.y < 0f32);
+                }
+                assert!(vel.y < 0f32);
+            }
+        }
+    }
+
+    #[test]
+    fn challenge_levels_drop_the_mutator_multiplier() {
+        let settings = Settings { mutators: mutators::Mutator::ALL.to_vec(), ..Settings::default() };
+        let run = Modifiers::from_settings(&settings);
+        assert!(run.score_multiplier > 1f32);
+        let level = run.without_mutators();
+        assert_eq!(level.score_multiplier, 1f32);
+        assert!(level.mutators.is_empty() && !level.fog && !level.wrap_sides);
+    }
+
+    #[test]
+    fn extra_balls_stand_out_from_every_block() {
+        let block_types = [
+            BlockType::Regular,
+            BlockType::SpawnBallOnDeath,
+            BlockType::Explosive,
+            BlockType::Indestructible,
+            BlockType::Portal(0),
+            BlockType::Portal(1),
+            BlockType::Portal(2),
+            BlockType::Trap,
+            BlockType::Brittle,
+        ];
+        let mut colors = vec![];
+        for block_type in block_types {
+            let mut block = Block::new(Vec2::ZERO, BLOCK_SIZE, block_type);
+            colors.push(block.color());
+            block.lives = 1;
+            colors.push(block.color());
+        }
+        for ball in SPAWNED_BALL_COLORS {
+            for color in colors.iter() {
+                let difference = (ball.r - color.r).abs() + (ball.g - color.g).abs() + (ball.b - color.b).abs();
+                assert!(difference > 0.25f32, "{:?} against {:?}", ball, color);
+            }
+        }
+    }
+
+    // Feeds float bit patterns and counts into a 64-bit FNV-1a hash, which unlike the standard library's
+    // hasher is the same on every platform and Rust version
+    struct Fnv(u64);
+
+    impl Fnv {
+        fn write(&mut self, value: u32) {
+            for byte in value.to_le_bytes() {
+                self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+            }
+        }
+    }
+
+    // The scripted input for the golden run: how many ticks to hold a direction, -1 for left, repeated
+    const GOLDEN_INPUT: [(u32, f32); 6] = [(90, -1f32), (30, 0f32), (150, 1f32), (45, 0f32), (60, -1f32), (20, 1f32)];
+    const GOLDEN_TICKS: u32 = 10_000;
+    const GOLDEN_CHECKPOINT: u32 = 2_500;
+    // Regenerate these deliberately, from the failure message, when a change is meant to move the ball
+    const GOLDEN_HASHES: [u64; 4] = [9903651446122660405, 16705059873213854682, 2368357904041226535, 923116607248051749];
+
+    fn state_hash(ball: &Ball, paddle: &Paddle, blocks: &[Block]) -> u64 {
+        let mut hash = Fnv(0xcbf29ce484222325);
+        for value in [ball.rect.x, ball.rect.y, ball.vel.x, ball.vel.y, paddle.rect.x] {
+            hash.write(value.to_bits());
+        }
+        hash.write(blocks.len() as u32);
+        for block in blocks.iter() {
+            hash.write(block.cell.0 as u32);
+            hash.write(block.cell.1 as u32);
+            hash.write(block.lives as u32);
+        }
+        hash.0
+    }
+
+    // Plays a seeded ball over a fixed level at the prediction step, with the paddle driven by
+    // GOLDEN_INPUT, using the game's own movement and collision code in the order a frame runs it. A ball
+    // that falls past the paddle is served again from the middle, which draws its heading from the RNG
+    fn golden_run() -> Vec<u64> {
+        rand::srand(146);
+        let field = Field { left: 0f32, right: 800f32 };
+        let level = Level::parse("version 3\nRRRRRRRR\nR.SEER.R\nIRRBBRRI\n.RRRRRR.\n").ok().unwrap();
+        let mut blocks = level.blocks_in(Rect::new(0f32, 40f32, 800f32, 240f32));
+        let mut paddle = Paddle::at(Rect::new(325f32, 700f32, PADDLE_SIZE.x, 20f32));
+        let serve = vec2(field.center() - BALL_SIZE * 0.5f32, 400f32);
+        let mut ball = Ball::new(serve, WHITE, 1f32);
+        let script = GOLDEN_INPUT.iter().flat_map(|&(ticks, direction)| (0..ticks).map(move |_| direction)).cycle();
+
+        let mut hashes = vec![];
+        for (tick, direction) in (1..=GOLDEN_TICKS).zip(script) {
+            paddle.prev_x = paddle.rect.x;
+            paddle.rect.x = (paddle.rect.x + direction * 600f32 * PREDICTION_STEP).clamp(field.left, field.right - paddle.rect.w);
+            ball.update(PREDICTION_STEP, field, false);
+
+            let swept = swept_paddle_collision(&mut ball, &paddle);
+            let surface = if swept.is_some() { Some(Surface::Paddle) } else { closest_surface(&ball, Some(&paddle.rect), &blocks) };
+            match surface {
+                Some(Surface::Paddle) => {
+                    let contact = swept.or_else(|| resolve_paddle_contact(&mut ball.rect, &mut ball.vel, &paddle.rect, 0f32));
+                    if contact.is_some() {
+                        ball.paddle_cooldown = PADDLE_COOLDOWN;
+                    }
+                },
+                Some(Surface::Block(i)) => {
+                    let contact = resolve_contact(&mut ball.rect, &mut ball.vel, &blocks[i].rect);
+                    if contact.is_some() && blocks[i].is_destructible() {
+                        blocks[i].lives -= 1;
+                        if blocks[i].lives == 0 {
+                            blocks.remove(i);
+                        }
+                    }
+                },
+                None => {},
+            }
+            if ball.rect.y > 800f32 {
+                ball = Ball::new(serve, WHITE, 1f32);
+            }
+            if tick % GOLDEN_CHECKPOINT == 0 {
+                hashes.push(state_hash(&ball, &paddle, &blocks));
+            }
+        }
+        hashes
+    }
+
+    #[test]
+    fn golden_run_matches_the_checked_in_hashes() {
+        // The same seed and input must always play out the same, run after run
+        let hashes = golden_run();
+        assert_eq!(hashes, golden_run());
+        assert_eq!(hashes, GOLDEN_HASHES, "the simulation changed; if that's intended, regenerate GOLDEN_HASHES");
     }
 }
@@ -0,0 +1,108 @@
+use macroquad::prelude::*;
+
+// Holding UP or DOWN moves once, waits REPEAT_DELAY, then keeps moving every REPEAT_INTERVAL
+const REPEAT_DELAY: f32 = 0.35f32;
+const REPEAT_INTERVAL: f32 = 0.08f32;
+const ENTRY_FONT_SIZE: u16 = 30;
+const SELECTED_FONT_SIZE: u16 = 38;
+const SELECTED_COLOR: Color = YELLOW;
+// Half the width of the arrows marking a scrolled list
+const SCROLL_ARROW_SIZE: f32 = 8f32;
+
+// A vertical list of entries with one highlighted, navigated with UP/DOWN and wrapping at both ends.
+// Each entry is whatever the screen wants back when it's chosen, like an action or a row index
+pub struct Menu<T: Copy> {
+    pub entries: Vec<T>,
+    pub selected: usize,
+    // Direction being held, -1 for up, and how long until it moves again
+    held: i32,
+    repeat_timer: f32,
+}
+
+impl<T: Copy> Menu<T> {
+    pub fn new(entries: Vec<T>) -> Self {
+        Self {
+            entries,
+            selected: 0,
+            held: 0,
+            repeat_timer: 0f32,
+        }
+    }
+
+    // Moves the highlight and returns the highlighted entry if `confirm` is set. The caller decides
+    // what confirms, so screens can share buffered keys like Space
+    pub fn handle_input(&mut self, confirm: bool) -> Option<T> {
+        let direction = match (is_key_down(KeyCode::Up), is_key_down(KeyCode::Down)) {
+            (true, false) => -1,
+            (false, true) => 1,
+            _ => 0,
+        };
+
+        if direction == 0 {
+            self.held = 0;
+        } else if direction != self.held {
+            self.held = direction;
+            self.repeat_timer = REPEAT_DELAY;
+            self.step(direction);
+        } else {
+            self.repeat_timer -= get_frame_time();
+            if self.repeat_timer <= 0f32 {
+                self.repeat_timer += REPEAT_INTERVAL;
+                self.step(direction);
+            }
+        }
+
+        confirm.then(|| self.entries[self.selected])
+    }
+
+    fn step(&mut self, direction: i32) {
+        let count = self.entries.len();
+        if count > 0 {
+            self.selected = (self.selected as i32 + direction).rem_euclid(count as i32) as usize;
+        }
+    }
+
+    // Draws one label per entry centered down the screen from `top`, the highlighted one larger and colored
+    pub fn draw(&self, labels: &[String], top: f32, spacing: f32, font: Font) {
+        self.draw_rows(labels, 0..labels.len(), top, spacing, font);
+    }
+
+    // Like draw, but only as many rows as fit above `bottom`, scrolled to keep the highlighted one in
+    // the middle where it can be. Arrows above and below show there's more to scroll to
+    pub fn draw_scrolling(&self, labels: &[String], top: f32, spacing: f32, bottom: f32, font: Font) {
+        let rows = (((bottom - top) / spacing) as usize + 1).clamp(1, labels.len().max(1));
+        let first = self.selected.saturating_sub(rows / 2).min(labels.len().saturating_sub(rows));
+        self.draw_rows(labels, first..(first + rows).min(labels.len()), top, spacing, font);
+
+        let x = screen_width() * 0.5f32;
+        if first > 0 {
+            let y = top - ENTRY_FONT_SIZE as f32 - 8f32;
+            draw_triangle(vec2(x - SCROLL_ARROW_SIZE, y), vec2(x + SCROLL_ARROW_SIZE, y), vec2(x, y - SCROLL_ARROW_SIZE), GRAY);
+        }
+        if first + rows < labels.len() {
+            let y = top + (rows - 1) as f32 * spacing + 16f32;
+            draw_triangle(vec2(x - SCROLL_ARROW_SIZE, y), vec2(x + SCROLL_ARROW_SIZE, y), vec2(x, y + SCROLL_ARROW_SIZE), GRAY);
+        }
+    }
+
+    // Draws the labels in `range`, the first of them at `top`
+    fn draw_rows(&self, labels: &[String], range: std::ops::Range<usize>, top: f32, spacing: f32, font: Font) {
+        let first = range.start;
+        for (i, label) in labels.iter().enumerate().take(range.end).skip(first) {
+            let selected = i == self.selected;
+            let font_size = if selected { SELECTED_FONT_SIZE } else { ENTRY_FONT_SIZE };
+            let dims = measure_text(label, Some(font), font_size, 1f32);
+            draw_text_ex(
+                label,
+                screen_width() * 0.5f32 - dims.width * 0.5f32,
+                top + (i - first) as f32 * spacing,
+                TextParams {
+                    font,
+                    font_size,
+                    color: if selected { SELECTED_COLOR } else { WHITE },
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
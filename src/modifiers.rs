@@ -0,0 +1,93 @@
+use crate::mutators::Mutator;
+use crate::settings::Settings;
+
+// Paddle width under the Tiny mutator
+const TINY_PADDLE_WIDTH: f32 = 0.6f32;
+// How much faster balls get per second of a level under Turbo, and the most it can add up to
+const TURBO_RAMP: f32 = 0.02f32;
+const TURBO_MAX: f32 = 1.6f32;
+
+// Adjustments laid over the base game when a run starts. Difficulty and power-ups work from the
+// modified values, so they stack with these rather than replacing them
+#[derive(Clone)]
+pub struct Modifiers {
+    // Multiplies the paddle's width
+    pub paddle_width: f32,
+    // Multiplies how fast every ball travels
+    pub ball_speed: f32,
+    // Shows the landing marker even with its own setting off
+    pub landing_marker: bool,
+    // Raises the forgiveness floor after every serve, whatever the difficulty or its own setting
+    pub forgiveness_floor: bool,
+    // Scores from the run are marked as assisted on the leaderboard
+    pub assisted: bool,
+    // Mutators in play, kept so they can be recorded with a leaderboard entry
+    pub mutators: Vec<Mutator>,
+    pub fog: bool,
+    pub memory: bool,
+    // Balls pass through the side walls and come back in at the other side
+    pub wrap_sides: bool,
+    // Extra ball speed gained per second of level time, as a fraction of the starting speed
+    pub speed_ramp: f32,
+    // Multiplies the score when the run ends
+    pub score_multiplier: f32,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        paddle_width: 1f32,
+        ball_speed: 1f32,
+        landing_marker: false,
+        forgiveness_floor: false,
+        assisted: false,
+        mutators: Vec::new(),
+        fog: false,
+        memory: false,
+        wrap_sides: false,
+        speed_ramp: 0f32,
+        score_multiplier: 1f32,
+    };
+
+    pub const ASSIST: Modifiers = Modifiers {
+        paddle_width: 1.3f32,
+        ball_speed: 0.85f32,
+        landing_marker: true,
+        forgiveness_floor: true,
+        assisted: true,
+        mutators: Vec::new(),
+        fog: false,
+        memory: false,
+        wrap_sides: false,
+        speed_ramp: 0f32,
+        score_multiplier: 1f32,
+    };
+
+    // Only read when a run starts, so toggling assist or a mutator mid-run waits for the next one.
+    // Mutators go on top of assist, so a Tiny assisted paddle is 0.6 of the already wider one
+    pub fn from_settings(settings: &Settings) -> Self {
+        let mut modifiers = if settings.assist { Self::ASSIST } else { Self::NONE };
+        for mutator in settings.mutators.iter() {
+            match mutator {
+                Mutator::Tiny => modifiers.paddle_width *= TINY_PADDLE_WIDTH,
+                Mutator::Fog => modifiers.fog = true,
+                Mutator::Turbo => modifiers.speed_ramp = TURBO_RAMP,
+                Mutator::Memory => modifiers.memory = true,
+                Mutator::Wrap => modifiers.wrap_sides = true,
+            }
+            modifiers.score_multiplier *= mutator.score_multiplier();
+        }
+        modifiers.mutators = settings.mutators.clone();
+        modifiers
+    }
+
+    // The same run with its mutators set aside, for a level with modifiers of its own. Their
+    // multiplier goes with them, since none of the handicaps it pays for are in play
+    pub fn without_mutators(&self) -> Self {
+        if self.assisted { Self::ASSIST } else { Self::NONE }
+    }
+
+    // How fast balls move this far into a level, relative to BALL_SPEED
+    pub fn ball_speed_at(&self, level_time: f32) -> f32 {
+        self.ball_speed * (1f32 + self.speed_ramp * level_time).min(TURBO_MAX)
+    }
+}
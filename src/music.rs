@@ -0,0 +1,69 @@
+use macroquad::audio::{load_sound_from_bytes, play_sound, set_sound_volume, PlaySoundParams, Sound};
+
+use crate::assets::load_asset;
+use crate::{Game, GameState};
+
+// No tracks ship with the game. Each layer plays once its file is added to res/music
+const BASE_LAYER_PATH: &str = "res/music/base.ogg";
+const PERCUSSION_LAYER_PATH: &str = "res/music/percussion.ogg";
+const TENSE_LAYER_PATH: &str = "res/music/tense.ogg";
+// The percussion layer comes in once less than this fraction of the board is left
+const PERCUSSION_THRESHOLD: f32 = 0.25f32;
+// How quickly layer volumes follow their targets, as a fraction of the gap closed per second
+const MUSIC_SMOOTHING: f32 = 1.5f32;
+
+struct Layer {
+    sound: Sound,
+    volume: f32,
+}
+
+// Layered background music. Every layer starts at the same moment and loops forever, and only
+// volumes change afterwards, so layers stay sample-aligned however often they fade in and out
+pub struct MusicDirector {
+    base: Option<Layer>,
+    percussion: Option<Layer>,
+    tense: Option<Layer>,
+}
+
+impl MusicDirector {
+    // Missing layers are skipped, so the music works with any subset of the files
+    pub async fn load() -> Self {
+        let base = load_sound(BASE_LAYER_PATH).await;
+        let percussion = load_sound(PERCUSSION_LAYER_PATH).await;
+        let tense = load_sound(TENSE_LAYER_PATH).await;
+
+        // Loading is done before any layer starts, so none of them gets a head start
+        Self {
+            base: base.map(start_layer),
+            percussion: percussion.map(start_layer),
+            tense: tense.map(start_layer),
+        }
+    }
+
+    // Reads the run each frame to decide which layers should be audible. Muting and pausing
+    // scale every layer together rather than stopping them, which would lose their alignment
+    pub fn update(&mut self, game: &Game, state: GameState, enabled: bool, dt: f32) {
+        let playing = matches!(state, GameState::Game);
+        let master = if enabled && state != GameState::Paused { 1f32 } else { 0f32 };
+        let percussion = if playing && 1f32 - game.level_progress() < PERCUSSION_THRESHOLD { 1f32 } else { 0f32 };
+        let tense = if playing && game.player_lives == 1 { 1f32 } else { 0f32 };
+
+        let smoothing = (MUSIC_SMOOTHING * dt).min(1f32);
+        for (layer, target) in [(&mut self.base, 1f32), (&mut self.percussion, percussion), (&mut self.tense, tense)] {
+            if let Some(layer) = layer.as_mut() {
+                layer.volume += (target * master - layer.volume) * smoothing;
+                set_sound_volume(layer.sound, layer.volume);
+            }
+        }
+    }
+}
+
+async fn load_sound(path: &str) -> Option<Sound> {
+    load_sound_from_bytes(&load_asset(path).await?).await.ok()
+}
+
+// Starts silent, leaving the director to fade it in
+fn start_layer(sound: Sound) -> Layer {
+    play_sound(sound, PlaySoundParams { looped: true, volume: 0f32 });
+    Layer { sound, volume: 0f32 }
+}
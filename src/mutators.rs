@@ -0,0 +1,107 @@
+use macroquad::prelude::*;
+
+use crate::lang::Lang;
+use crate::menu::Menu;
+use crate::settings::Settings;
+use crate::draw_centered_text;
+
+// Most mutators multiply the final score by this, so two are worth 1.5625x. Memory is worth more
+const MUTATOR_SCORE_MULTIPLIER: f32 = 1.25f32;
+const MEMORY_SCORE_MULTIPLIER: f32 = 2f32;
+
+// Optional rules that make a run harder for a bigger score. Any number can be on at once
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mutator {
+    // The paddle at 60% width
+    Tiny,
+    // Only the area around each ball and along the paddle's row is lit
+    Fog,
+    // Balls keep speeding up the longer a level goes on
+    Turbo,
+    // The board is only shown for the first moments of a level, then just where it's hit
+    Memory,
+    // The side walls are open, so a ball leaving one side comes back in at the other
+    Wrap,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 5] = [Mutator::Tiny, Mutator::Fog, Mutator::Turbo, Mutator::Memory, Mutator::Wrap];
+
+    // Written to the settings file and to leaderboard entries
+    pub fn name(self) -> &'static str {
+        match self {
+            Mutator::Tiny => "tiny",
+            Mutator::Fog => "fog",
+            Mutator::Turbo => "turbo",
+            Mutator::Memory => "memory",
+            Mutator::Wrap => "wrap",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|mutator| mutator.name() == name)
+    }
+
+    pub fn text_id(self) -> &'static str {
+        match self {
+            Mutator::Tiny => "mutator.tiny",
+            Mutator::Fog => "mutator.fog",
+            Mutator::Turbo => "mutator.turbo",
+            Mutator::Memory => "mutator.memory",
+            Mutator::Wrap => "mutator.wrap",
+        }
+    }
+
+    pub fn score_multiplier(self) -> f32 {
+        match self {
+            Mutator::Memory => MEMORY_SCORE_MULTIPLIER,
+            _ => MUTATOR_SCORE_MULTIPLIER,
+        }
+    }
+}
+
+// What the main loop should do after a mutators frame
+pub enum MutatorsAction {
+    Back,
+}
+
+// Toggles for each mutator, with the score multiplier they add up to. Changes are saved on the way
+// out and apply from the next run, like assist mode
+pub struct MutatorsScreen {
+    menu: Menu<Mutator>,
+}
+
+impl MutatorsScreen {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new(Mutator::ALL.to_vec()),
+        }
+    }
+
+    pub fn update(&mut self, settings: &mut Settings, confirm: bool) -> Option<MutatorsAction> {
+        if let Some(mutator) = self.menu.handle_input(confirm || is_key_pressed(KeyCode::Enter)) {
+            settings.toggle_mutator(mutator);
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            settings.save();
+            return Some(MutatorsAction::Back);
+        }
+        None
+    }
+
+    pub fn draw(&self, settings: &Settings, lang: &Lang, font: Font) {
+        draw_centered_text(lang.tr("mutators.title"), 100f32, font);
+        let labels: Vec<String> = Mutator::ALL
+            .iter()
+            .map(|mutator| {
+                let value = if settings.mutators.contains(mutator) { "on" } else { "off" };
+                format!("{}: {}", lang.tr(mutator.text_id()), lang.tr(value))
+            })
+            .collect();
+        self.menu.draw(&labels, 200f32, 50f32, font);
+
+        let multiplier: f32 = settings.mutators.iter().map(|mutator| mutator.score_multiplier()).product();
+        draw_centered_text(&lang.tr("mutators.multiplier").replace("{n}", &format!("{:.2}", multiplier)), 470f32, font);
+        draw_centered_text(lang.tr("mutators.help"), screen_height() - 60f32, font);
+    }
+}
@@ -0,0 +1,155 @@
+// Global high-score submission over plain HTTP
+// Requests run on a background thread and report back through a channel polled each frame,
+// so a slow or missing server can never stall the render loop
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const TOP_SCORES: usize = 10;
+
+pub struct ScoreSubmission {
+    pub initials: String,
+    pub score: i32,
+    pub seed: u64,
+}
+
+type Reply = Result<Vec<(String, i32)>, String>;
+
+pub struct Online {
+    url: String,
+    pub top_scores: Vec<(String, i32)>,
+    // Set when the last request failed, cleared by the next one that succeeds
+    pub offline: bool,
+    sender: Sender<Reply>,
+    receiver: Receiver<Reply>,
+}
+
+impl Online {
+    // Starts fetching the global list straight away so it's ready by the time the menu shows
+    pub fn new(url: &str) -> Self {
+        let (sender, receiver) = channel();
+        let online = Self {
+            url: url.to_string(),
+            top_scores: Vec::new(),
+            offline: false,
+            sender,
+            receiver,
+        };
+        online.spawn(None);
+        online
+    }
+
+    // Posts the score, then refreshes the global list
+    pub fn submit(&self, submission: ScoreSubmission) {
+        self.spawn(Some(submission));
+    }
+
+    // Picks up any finished requests without blocking
+    pub fn poll(&mut self) {
+        while let Ok(reply) = self.receiver.try_recv() {
+            match reply {
+                Ok(top_scores) => {
+                    self.top_scores = top_scores;
+                    self.offline = false;
+                },
+                Err(_) => self.offline = true,
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn spawn(&self, submission: Option<ScoreSubmission>) {
+        let url = self.url.clone();
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            let reply = submission
+                .map_or(Ok(()), |submission| post_score(&url, &submission))
+                .and_then(|_| fetch_top_scores(&url));
+            let _ = sender.send(reply);
+        });
+    }
+
+    // There are no threads or raw sockets on the web, so WASM builds are always offline
+    #[cfg(target_arch = "wasm32")]
+    fn spawn(&self, _submission: Option<ScoreSubmission>) {
+        let _ = self.sender.send(Err("networking isn't supported on the web".to_string()));
+    }
+}
+
+fn post_score(url: &str, submission: &ScoreSubmission) -> Result<(), String> {
+    let body = format!(
+        "{{\"initials\":\"{}\",\"score\":{},\"seed\":{}}}",
+        submission.initials, submission.score, submission.seed,
+    );
+    request(url, "POST", &body).map(|_| ())
+}
+
+// Expects a JSON array of {"initials": "...", "score": n} objects, best first
+fn fetch_top_scores(url: &str) -> Result<Vec<(String, i32)>, String> {
+    let body = request(url, "GET", "")?;
+    let mut scores = Vec::new();
+    for entry in body.split('}') {
+        let initials = json_field(entry, "initials").map(|value| value.trim_matches('"').to_string());
+        let score = json_field(entry, "score").and_then(|value| value.parse().ok());
+        if let (Some(initials), Some(score)) = (initials, score) {
+            scores.push((initials, score));
+        }
+    }
+    scores.truncate(TOP_SCORES);
+    Ok(scores)
+}
+
+// Pulls the raw value of `"key": value` out of a flat JSON object
+fn json_field<'a>(object: &'a str, key: &str) -> Option<&'a str> {
+    let start = object.find(&format!("\"{}\"", key))? + key.len() + 2;
+    let value = object[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = if let Some(quoted) = value.strip_prefix('"') {
+        quoted.find('"')? + 2
+    } else {
+        value.find([',', '}', ']']).unwrap_or(value.len())
+    };
+    Some(value[..end].trim())
+}
+
+// HTTP/1.0 keeps the response free of chunked encoding, so the body is everything after the headers
+fn request(url: &str, method: &str, body: &str) -> Result<String, String> {
+    let rest = url.strip_prefix("http://").ok_or("only http:// urls are supported")?;
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let path = if path.is_empty() { "/" } else { path };
+    let (host, port) = match authority.split_once(':') {
+        Some((host, port)) => (host, port.parse::<u16>().map_err(|error| error.to_string())?),
+        None => (authority, 80),
+    };
+
+    let address = (host, port)
+        .to_socket_addrs()
+        .map_err(|error| error.to_string())?
+        .next()
+        .ok_or("couldn't resolve host")?;
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT).map_err(|error| error.to_string())?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(|error| error.to_string())?;
+    stream.set_write_timeout(Some(TIMEOUT)).map_err(|error| error.to_string())?;
+
+    write!(
+        stream,
+        "{} {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        host,
+        body.len(),
+        body,
+    )
+    .map_err(|error| error.to_string())?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).map_err(|error| error.to_string())?;
+
+    let (head, body) = response.split_once("\r\n\r\n").ok_or("malformed response")?;
+    let status = head.split_whitespace().nth(1).unwrap_or_default();
+    if !status.starts_with('2') {
+        return Err(format!("server answered {}", status));
+    }
+    Ok(body.to_string())
+}
@@ -0,0 +1,82 @@
+use crate::level::CAMPAIGN_LEVELS;
+
+pub const PROGRESS_PATH: &str = "progress.txt";
+
+// Best results on one campaign level, None and zero stars until it's been cleared
+#[derive(Clone, Copy, Default)]
+pub struct LevelBest {
+    pub score: Option<i32>,
+    pub time: Option<f32>,
+    pub stars: u8,
+}
+
+// How far through the campaign the player has got, saved as `unlocked N` followed by one
+// `level index score seconds stars` line per cleared level
+pub struct Progress {
+    // Levels below this index can be played. The first is always open
+    pub unlocked: usize,
+    best: Vec<LevelBest>,
+}
+
+impl Progress {
+    // Lines that don't parse are skipped, so a damaged file at worst relocks some levels. So are clears of
+    // levels past the end of the campaign, which would otherwise size the list to whatever index they name
+    pub fn load() -> Self {
+        let mut progress = Self {
+            unlocked: 1,
+            best: Vec::new(),
+        };
+        let Ok(text) = std::fs::read_to_string(PROGRESS_PATH) else {
+            return progress;
+        };
+        for line in text.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["unlocked", count] => progress.unlocked = count.parse().unwrap_or(1).max(1),
+                // Files from before star ratings have no stars field, and count as one star clears
+                ["level", index, score, time, stars @ ..] if stars.len() <= 1 => {
+                    let stars = stars.first().map_or(Ok(1), |stars| stars.parse());
+                    if let (Ok(index), Ok(score), Ok(time), Ok(stars)) = (index.parse::<usize>(), score.parse(), time.parse(), stars) {
+                        if index < CAMPAIGN_LEVELS.len() {
+                            progress.record_clear(index, score, time, stars);
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+        progress
+    }
+
+    // Best effort like the other save files
+    pub fn save(&self) {
+        let mut text = format!("unlocked {}\n", self.unlocked);
+        for (i, best) in self.best.iter().enumerate() {
+            if let (Some(score), Some(time)) = (best.score, best.time) {
+                text.push_str(&format!("level {} {} {:.3} {}\n", i, score, time, best.stars));
+            }
+        }
+        let _ = std::fs::write(PROGRESS_PATH, text);
+    }
+
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        index < self.unlocked
+    }
+
+    pub fn best(&self, index: usize) -> LevelBest {
+        self.best.get(index).copied().unwrap_or_default()
+    }
+
+    // Keeps the higher score, the faster time, and the most stars separately, so replaying a level
+    // can only ever improve it, and opens the next level
+    pub fn record_clear(&mut self, index: usize, score: i32, time: f32, stars: u8) {
+        if self.best.len() <= index {
+            self.best.resize(index + 1, LevelBest::default());
+        }
+        let best = &mut self.best[index];
+        best.score = Some(best.score.map_or(score, |best| best.max(score)));
+        best.time = Some(best.time.map_or(time, |best| best.min(time)));
+        best.stars = best.stars.max(stars);
+        self.unlocked = self.unlocked.max(index + 2);
+    }
+}
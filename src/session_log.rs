@@ -0,0 +1,91 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use macroquad::miniquad;
+
+use crate::settings::Settings;
+use crate::{Game, GameEvent};
+
+pub const SESSIONS_DIR: &str = "sessions";
+
+// Machine-readable record of a single run, one JSON object per line
+// The first line is a header describing the run so logs can be analyzed offline
+pub struct SessionLog {
+    writer: BufWriter<File>,
+}
+
+impl SessionLog {
+    // Opens sessions/<timestamp>.jsonl and writes the header. Logging is best effort,
+    // so any IO failure just means the run goes unlogged
+    pub fn create(game: &Game, settings: &Settings) -> Option<Self> {
+        std::fs::create_dir_all(SESSIONS_DIR).ok()?;
+        let timestamp = (miniquad::date::now() * 1000f64) as u64;
+        let file = File::create(format!("{}/{}.jsonl", SESSIONS_DIR, timestamp)).ok()?;
+
+        let config = settings
+            .values()
+            .iter()
+            .map(|(key, value)| format!("{}:{}", json_string(key), json_string(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut log = Self {
+            writer: BufWriter::new(file),
+        };
+        log.write_line(&format!(
+            "{{\"version\":{},\"seed\":{},\"mode\":{},\"config\":{{{}}}}}",
+            json_string(env!("CARGO_PKG_VERSION")),
+            game.seed,
+            json_string(game.mode.name()),
+            config,
+        ));
+        Some(log)
+    }
+
+    pub fn record(&mut self, event: &GameEvent, time: f32) {
+        let fields = match event {
+            GameEvent::BlockDestroyed { pos, block_type } => format!(
+                "\"event\":\"block_destroyed\",\"x\":{:.1},\"y\":{:.1},\"type\":{}",
+                pos.x,
+                pos.y,
+                json_string(block_type.name()),
+            ),
+            GameEvent::LifeLost { lives_left } => format!("\"event\":\"life_lost\",\"lives_left\":{}", lives_left),
+            GameEvent::PowerUpCollected(kind) => {
+                format!("\"event\":\"power_up_collected\",\"kind\":{}", json_string(kind.name()))
+            },
+            GameEvent::LevelCleared => "\"event\":\"level_cleared\"".to_string(),
+            GameEvent::Chain { length, bonus } => {
+                format!("\"event\":\"chain\",\"length\":{},\"bonus\":{}", length, bonus)
+            },
+            GameEvent::GameOver { score } => format!("\"event\":\"game_over\",\"score\":{}", score),
+            GameEvent::BallUnstuck { pos } => format!("\"event\":\"ball_unstuck\",\"x\":{:.1},\"y\":{:.1}", pos.x, pos.y),
+        };
+        self.write_line(&format!("{{\"t\":{:.3},{}}}", time, fields));
+    }
+
+    // Lines are buffered in memory until this is called, which happens on state transitions
+    pub fn flush(&mut self) {
+        let _ = self.writer.flush();
+    }
+
+    fn write_line(&mut self, line: &str) {
+        let _ = writeln!(self.writer, "{}", line);
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            character if character.is_control() => escaped.push_str(&format!("\\u{:04x}", character as u32)),
+            character => escaped.push(character),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
@@ -0,0 +1,421 @@
+use crate::lang::LANGUAGES;
+use crate::mutators::Mutator;
+use crate::skins::SKINS;
+
+pub const SETTINGS_PATH: &str = "settings.cfg";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FpsCap {
+    Uncapped,
+    Fps60,
+    Fps120,
+}
+
+impl FpsCap {
+    // Seconds each frame should take, or None to run as fast as the loop allows
+    pub fn frame_time(self) -> Option<f64> {
+        match self {
+            FpsCap::Uncapped => None,
+            FpsCap::Fps60 => Some(1f64 / 60f64),
+            FpsCap::Fps120 => Some(1f64 / 120f64),
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            FpsCap::Uncapped => FpsCap::Fps60,
+            FpsCap::Fps60 => FpsCap::Fps120,
+            FpsCap::Fps120 => FpsCap::Uncapped,
+        }
+    }
+
+    fn previous(self) -> Self {
+        self.next().next()
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            FpsCap::Uncapped => "uncapped",
+            FpsCap::Fps60 => "60",
+            FpsCap::Fps120 => "120",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "uncapped" => Some(FpsCap::Uncapped),
+            "60" => Some(FpsCap::Fps60),
+            "120" => Some(FpsCap::Fps120),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    fn previous(self) -> Self {
+        self.next().next()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PaddleSpeed {
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl PaddleSpeed {
+    fn next(self) -> Self {
+        match self {
+            PaddleSpeed::Slow => PaddleSpeed::Normal,
+            PaddleSpeed::Normal => PaddleSpeed::Fast,
+            PaddleSpeed::Fast => PaddleSpeed::Slow,
+        }
+    }
+
+    fn previous(self) -> Self {
+        self.next().next()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PaddleSpeed::Slow => "slow",
+            PaddleSpeed::Normal => "normal",
+            PaddleSpeed::Fast => "fast",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "slow" => Some(PaddleSpeed::Slow),
+            "normal" => Some(PaddleSpeed::Normal),
+            "fast" => Some(PaddleSpeed::Fast),
+            _ => None,
+        }
+    }
+
+    // Top speed of the paddle under the arrow keys. Dashes cover their own fixed distance whatever this is
+    pub fn pixels_per_second(self) -> f32 {
+        match self {
+            PaddleSpeed::Slow => 500f32,
+            PaddleSpeed::Normal => 700f32,
+            PaddleSpeed::Fast => 950f32,
+        }
+    }
+}
+
+// Player preferences, saved as `key = value` lines so the file is easy to edit by hand
+pub struct Settings {
+    pub fps_cap: FpsCap,
+    pub difficulty: Difficulty,
+    // Window options are read before the window exists, so changes apply on the next launch
+    pub window_width: i32,
+    pub window_height: i32,
+    pub fullscreen: bool,
+    // Adds a linked pair of indestructible portal blocks to generated boards
+    pub portals: bool,
+    // Turns three random blocks on each generated board into ones that release an extra ball
+    pub spawn_ball_blocks: bool,
+    // Writes every run's events to sessions/ as JSON lines, also enabled by --log-session
+    pub log_sessions: bool,
+    // Forfeits any ball that drops past a line above the paddle without the paddle underneath it
+    pub danger_line: bool,
+    // Catches the first ball lost shortly after serving from a lost life. Never applies on Hard
+    pub forgiveness_floor: bool,
+    // Swaps which way LEFT and RIGHT move the paddle, for left-handed play
+    pub invert_controls: bool,
+    pub paddle_speed: PaddleSpeed,
+    // Assist that marks where a falling ball will reach the paddle's row
+    pub landing_marker: bool,
+    // Assist that drifts the paddle towards the next ball to land while no direction is held
+    pub auto_aim: bool,
+    // Bigger paddle, slower balls, and the landing marker and forgiveness floor forced on, from the
+    // next run. Independent of difficulty, and assisted leaderboard times are marked
+    pub assist: bool,
+    // Hard mutators for the next run, picked on their own screen off the menu
+    pub mutators: Vec<Mutator>,
+    pub music: bool,
+    // Bounces back the first ball lost each level instead of costing a life. Only applies on Easy
+    pub safety_net: bool,
+    // Turns off screen shake and flashes, limits particles, and holds the background still
+    pub reduced_motion: bool,
+    // Keeps the playfield no wider than 16:9, centered with empty margins on wider windows
+    pub fixed_aspect: bool,
+    // Slows the balls briefly as one closes in on the last block of a level
+    pub last_block_slow_motion: bool,
+    // Moves the paddle at full speed the instant a key is down and stops it dead on release, instead
+    // of speeding up and coasting
+    pub classic_movement: bool,
+    // Lets Q and E tip the paddle to angle its bounces by hand
+    pub paddle_tilt: bool,
+    // Code of the language on-screen text is shown in, one of LANGUAGES
+    pub language: String,
+    // Name of the equipped skin, one of skins::SKINS
+    pub skin: String,
+    // Three letters sent along with online high scores
+    pub initials: String,
+    // Where the online feature posts scores and fetches the global top 10 from
+    pub online_url: String,
+    // Shown in the window's title bar. Only read as the window opens
+    pub window_title: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            fps_cap: FpsCap::Uncapped,
+            difficulty: Difficulty::Normal,
+            window_width: 1280,
+            window_height: 720,
+            fullscreen: false,
+            portals: false,
+            spawn_ball_blocks: true,
+            log_sessions: false,
+            danger_line: false,
+            forgiveness_floor: true,
+            invert_controls: false,
+            paddle_speed: PaddleSpeed::Normal,
+            landing_marker: false,
+            auto_aim: false,
+            assist: false,
+            mutators: Vec::new(),
+            music: true,
+            safety_net: true,
+            reduced_motion: false,
+            fixed_aspect: false,
+            last_block_slow_motion: true,
+            classic_movement: false,
+            paddle_tilt: false,
+            language: "en".to_string(),
+            skin: "classic".to_string(),
+            initials: "AAA".to_string(),
+            online_url: "http://127.0.0.1:8080/scores".to_string(),
+            window_title: "Breakout".to_string(),
+        }
+    }
+}
+
+impl Settings {
+    // A missing file, or any line that doesn't parse, leaves that setting at its default
+    pub fn load() -> Self {
+        let mut settings = Settings::default();
+        if let Ok(text) = std::fs::read_to_string(SETTINGS_PATH) {
+            for line in text.lines() {
+                if let Some((key, value)) = line.split_once('=') {
+                    settings.set(key.trim(), value.trim());
+                }
+            }
+        }
+        settings
+    }
+
+    // Saving is best effort, a read-only directory just means settings don't stick
+    pub fn save(&self) {
+        let text: String = self
+            .values()
+            .iter()
+            .map(|(key, value)| format!("{} = {}\n", key, value))
+            .collect();
+        let _ = std::fs::write(SETTINGS_PATH, text);
+    }
+
+    // Every setting as it's written to the settings file
+    pub fn values(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("fps_cap", self.fps_cap.name().to_string()),
+            ("difficulty", self.difficulty.name().to_string()),
+            ("window_width", self.window_width.to_string()),
+            ("window_height", self.window_height.to_string()),
+            ("fullscreen", self.fullscreen.to_string()),
+            ("portals", self.portals.to_string()),
+            ("spawn_ball_blocks", self.spawn_ball_blocks.to_string()),
+            ("log_sessions", self.log_sessions.to_string()),
+            ("danger_line", self.danger_line.to_string()),
+            ("forgiveness_floor", self.forgiveness_floor.to_string()),
+            ("invert_controls", self.invert_controls.to_string()),
+            ("paddle_speed", self.paddle_speed.name().to_string()),
+            ("landing_marker", self.landing_marker.to_string()),
+            ("auto_aim", self.auto_aim.to_string()),
+            ("assist", self.assist.to_string()),
+            ("mutators", self.mutators.iter().map(|mutator| mutator.name()).collect::<Vec<_>>().join(",")),
+            ("music", self.music.to_string()),
+            ("safety_net", self.safety_net.to_string()),
+            ("reduced_motion", self.reduced_motion.to_string()),
+            ("fixed_aspect", self.fixed_aspect.to_string()),
+            ("last_block_slow_motion", self.last_block_slow_motion.to_string()),
+            ("classic_movement", self.classic_movement.to_string()),
+            ("paddle_tilt", self.paddle_tilt.to_string()),
+            ("language", self.language.clone()),
+            ("skin", self.skin.clone()),
+            ("initials", self.initials.clone()),
+            ("online_url", self.online_url.clone()),
+            ("window_title", self.window_title.clone()),
+        ]
+    }
+
+    // Applies one value from the settings file, ignoring it if it doesn't parse
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "fps_cap" => self.fps_cap = FpsCap::from_name(value).unwrap_or(self.fps_cap),
+            "difficulty" => self.difficulty = Difficulty::from_name(value).unwrap_or(self.difficulty),
+            "window_width" => self.window_width = value.parse().unwrap_or(self.window_width),
+            "window_height" => self.window_height = value.parse().unwrap_or(self.window_height),
+            "fullscreen" => self.fullscreen = value.parse().unwrap_or(self.fullscreen),
+            "portals" => self.portals = value.parse().unwrap_or(self.portals),
+            "spawn_ball_blocks" => self.spawn_ball_blocks = value.parse().unwrap_or(self.spawn_ball_blocks),
+            "log_sessions" => self.log_sessions = value.parse().unwrap_or(self.log_sessions),
+            "danger_line" => self.danger_line = value.parse().unwrap_or(self.danger_line),
+            "forgiveness_floor" => self.forgiveness_floor = value.parse().unwrap_or(self.forgiveness_floor),
+            "invert_controls" => self.invert_controls = value.parse().unwrap_or(self.invert_controls),
+            "paddle_speed" => self.paddle_speed = PaddleSpeed::from_name(value).unwrap_or(self.paddle_speed),
+            "landing_marker" => self.landing_marker = value.parse().unwrap_or(self.landing_marker),
+            "auto_aim" => self.auto_aim = value.parse().unwrap_or(self.auto_aim),
+            "assist" => self.assist = value.parse().unwrap_or(self.assist),
+            // Read back in Mutator::ALL order like toggle_mutator keeps them, so a name listed twice counts once
+            "mutators" => {
+                let named: Vec<Mutator> = value.split(',').filter_map(|name| Mutator::from_name(name.trim())).collect();
+                self.mutators = Mutator::ALL.iter().copied().filter(|mutator| named.contains(mutator)).collect();
+            },
+            "music" => self.music = value.parse().unwrap_or(self.music),
+            "safety_net" => self.safety_net = value.parse().unwrap_or(self.safety_net),
+            "reduced_motion" => self.reduced_motion = value.parse().unwrap_or(self.reduced_motion),
+            "fixed_aspect" => self.fixed_aspect = value.parse().unwrap_or(self.fixed_aspect),
+            "last_block_slow_motion" => self.last_block_slow_motion = value.parse().unwrap_or(self.last_block_slow_motion),
+            "classic_movement" => self.classic_movement = value.parse().unwrap_or(self.classic_movement),
+            "paddle_tilt" => self.paddle_tilt = value.parse().unwrap_or(self.paddle_tilt),
+            "language" if LANGUAGES.iter().any(|(code, _)| *code == value) => self.language = value.to_string(),
+            "skin" if SKINS.iter().any(|skin| skin.name == value) => self.skin = value.to_string(),
+            "initials" => {
+                let initials: String = value
+                    .chars()
+                    .filter(|character| character.is_ascii_alphabetic())
+                    .take(3)
+                    .collect();
+                if initials.len() == 3 {
+                    self.initials = initials.to_ascii_uppercase();
+                }
+            },
+            "online_url" => self.online_url = value.to_string(),
+            "window_title" => self.window_title = value.to_string(),
+            _ => {},
+        }
+    }
+
+    // Text ids of every entry on the settings screen and their current values, in display order
+    // Values are passed through the language lookup too, so on/off and names get translated
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let language = LANGUAGES
+            .iter()
+            .find(|(code, _)| *code == self.language)
+            .map_or(self.language.clone(), |(_, name)| name.to_string());
+        vec![
+            ("settings.fps_cap", self.fps_cap.name().to_string()),
+            ("settings.fullscreen", on_off(self.fullscreen)),
+            ("settings.portals", on_off(self.portals)),
+            ("settings.log_sessions", on_off(self.log_sessions)),
+            ("settings.danger_line", on_off(self.danger_line)),
+            ("settings.spawn_ball_blocks", on_off(self.spawn_ball_blocks)),
+            ("settings.difficulty", self.difficulty.name().to_string()),
+            ("settings.forgiveness_floor", on_off(self.forgiveness_floor)),
+            ("settings.language", language),
+            ("settings.invert_controls", on_off(self.invert_controls)),
+            ("settings.music", on_off(self.music)),
+            ("settings.safety_net", on_off(self.safety_net)),
+            ("settings.reduced_motion", on_off(self.reduced_motion)),
+            ("settings.paddle_speed", self.paddle_speed.name().to_string()),
+            ("settings.landing_marker", on_off(self.landing_marker)),
+            ("settings.auto_aim", on_off(self.auto_aim)),
+            ("settings.assist", on_off(self.assist)),
+            ("settings.fixed_aspect", on_off(self.fixed_aspect)),
+            ("settings.last_block_slow_motion", on_off(self.last_block_slow_motion)),
+            ("settings.classic_movement", on_off(self.classic_movement)),
+            ("settings.paddle_tilt", on_off(self.paddle_tilt)),
+        ]
+    }
+
+    // Steps the entry at `index` to its next or previous value
+    pub fn change(&mut self, index: usize, forward: bool) {
+        match index {
+            0 => self.fps_cap = if forward { self.fps_cap.next() } else { self.fps_cap.previous() },
+            1 => self.fullscreen = !self.fullscreen,
+            2 => self.portals = !self.portals,
+            3 => self.log_sessions = !self.log_sessions,
+            4 => self.danger_line = !self.danger_line,
+            5 => self.spawn_ball_blocks = !self.spawn_ball_blocks,
+            6 => self.difficulty = if forward { self.difficulty.next() } else { self.difficulty.previous() },
+            7 => self.forgiveness_floor = !self.forgiveness_floor,
+            8 => {
+                let current = LANGUAGES.iter().position(|(code, _)| *code == self.language).unwrap_or(0);
+                let step = if forward { 1 } else { LANGUAGES.len() - 1 };
+                self.language = LANGUAGES[(current + step) % LANGUAGES.len()].0.to_string();
+            },
+            9 => self.invert_controls = !self.invert_controls,
+            10 => self.music = !self.music,
+            11 => self.safety_net = !self.safety_net,
+            12 => self.reduced_motion = !self.reduced_motion,
+            13 => self.paddle_speed = if forward { self.paddle_speed.next() } else { self.paddle_speed.previous() },
+            14 => self.landing_marker = !self.landing_marker,
+            15 => self.auto_aim = !self.auto_aim,
+            16 => self.assist = !self.assist,
+            17 => self.fixed_aspect = !self.fixed_aspect,
+            18 => self.last_block_slow_motion = !self.last_block_slow_motion,
+            19 => self.classic_movement = !self.classic_movement,
+            20 => self.paddle_tilt = !self.paddle_tilt,
+            _ => {},
+        }
+    }
+
+    // Keeps the list in Mutator::ALL order, so the settings file and leaderboard always list them the same way
+    pub fn toggle_mutator(&mut self, mutator: Mutator) {
+        let on = !self.mutators.contains(&mutator);
+        self.mutators = Mutator::ALL
+            .iter()
+            .copied()
+            .filter(|other| if *other == mutator { on } else { self.mutators.contains(other) })
+            .collect();
+    }
+
+    pub fn forgiveness_floor_active(&self) -> bool {
+        self.forgiveness_floor && self.difficulty != Difficulty::Hard
+    }
+
+    pub fn safety_net_active(&self) -> bool {
+        self.safety_net && self.difficulty == Difficulty::Easy
+    }
+}
+
+fn on_off(value: bool) -> String {
+    if value { "on" } else { "off" }.to_string()
+}
@@ -0,0 +1,170 @@
+use macroquad::prelude::*;
+
+use crate::lang::Lang;
+use crate::stats::Stats;
+
+// Most positions a ball remembers for its trail, whatever the skin asks for
+pub const MAX_TRAIL_LENGTH: usize = 12;
+
+// Faded copies of the ball drawn along where it has just been
+pub struct Trail {
+    pub color: Color,
+    pub length: usize,
+}
+
+// What the player has to have done, counted from lifetime stats, before a skin can be picked
+pub enum Unlock {
+    Always,
+    TotalScore(u64),
+    BlocksDestroyed(u64),
+    LongestChain(u32),
+}
+
+// How the paddle and ball look. The skin only sets the served ball's color, since extra balls
+// keep their own colors to stay easy to tell apart
+pub struct Skin {
+    // Written to the settings file
+    pub name: &'static str,
+    pub text_id: &'static str,
+    pub paddle: Color,
+    // A band along the middle of the paddle
+    pub stripe: Option<Color>,
+    pub ball: Color,
+    pub round: bool,
+    pub trail: Option<Trail>,
+    pub unlock: Unlock,
+}
+
+pub const SKINS: [Skin; 6] = [
+    Skin {
+        name: "classic",
+        text_id: "skin.classic",
+        paddle: BLACK,
+        stripe: None,
+        ball: WHITE,
+        round: false,
+        trail: None,
+        unlock: Unlock::Always,
+    },
+    Skin {
+        name: "ocean",
+        text_id: "skin.ocean",
+        paddle: DARKBLUE,
+        stripe: Some(SKYBLUE),
+        ball: SKYBLUE,
+        round: true,
+        trail: Some(Trail { color: SKYBLUE, length: 6 }),
+        unlock: Unlock::TotalScore(2_000),
+    },
+    Skin {
+        name: "forest",
+        text_id: "skin.forest",
+        paddle: DARKGREEN,
+        stripe: Some(LIME),
+        ball: LIME,
+        round: false,
+        trail: Some(Trail { color: GREEN, length: 6 }),
+        unlock: Unlock::BlocksDestroyed(1_000),
+    },
+    Skin {
+        name: "ember",
+        text_id: "skin.ember",
+        paddle: MAROON,
+        stripe: Some(ORANGE),
+        ball: ORANGE,
+        round: true,
+        trail: Some(Trail { color: ORANGE, length: 10 }),
+        unlock: Unlock::TotalScore(10_000),
+    },
+    Skin {
+        name: "chain",
+        text_id: "skin.chain",
+        paddle: DARKPURPLE,
+        stripe: Some(PINK),
+        ball: PINK,
+        round: true,
+        trail: Some(Trail { color: VIOLET, length: 12 }),
+        unlock: Unlock::LongestChain(8),
+    },
+    Skin {
+        name: "gold",
+        text_id: "skin.gold",
+        paddle: GOLD,
+        stripe: Some(YELLOW),
+        ball: YELLOW,
+        round: true,
+        trail: Some(Trail { color: GOLD, length: 12 }),
+        unlock: Unlock::TotalScore(50_000),
+    },
+];
+
+// Unknown names fall back to the first skin
+pub fn find(name: &str) -> &'static Skin {
+    SKINS.iter().find(|skin| skin.name == name).unwrap_or(&SKINS[0])
+}
+
+impl Skin {
+    pub fn is_unlocked(&self, stats: &Stats) -> bool {
+        match self.unlock {
+            Unlock::Always => true,
+            Unlock::TotalScore(score) => stats.total_score >= score,
+            Unlock::BlocksDestroyed(blocks) => stats.blocks_destroyed >= blocks,
+            Unlock::LongestChain(chain) => stats.longest_chain >= chain,
+        }
+    }
+
+    pub fn unlock_text(&self, lang: &Lang) -> String {
+        match self.unlock {
+            Unlock::Always => String::new(),
+            Unlock::TotalScore(score) => lang.tr("skin.unlock_score").replace("{n}", &score.to_string()),
+            Unlock::BlocksDestroyed(blocks) => lang.tr("skin.unlock_blocks").replace("{n}", &blocks.to_string()),
+            Unlock::LongestChain(chain) => lang.tr("skin.unlock_chain").replace("{n}", &chain.to_string()),
+        }
+    }
+
+    // `color` is the paddle's color this frame, which flashes away from the skin's own on a hit
+    // Turned `tilt` radians about the rect's center, which the stripe shares
+    pub fn draw_paddle(&self, rect: Rect, tilt: f32, color: Color) {
+        draw_turned_rectangle(rect, tilt, color);
+        if let Some(stripe) = self.stripe {
+            draw_turned_rectangle(Rect::new(rect.x, rect.y + rect.h * 0.35f32, rect.w, rect.h * 0.3f32), tilt, stripe);
+        }
+    }
+
+    pub fn draw_ball(&self, rect: Rect, color: Color) {
+        if self.round {
+            draw_circle(rect.x + rect.w * 0.5f32, rect.y + rect.h * 0.5f32, rect.w * 0.5f32, color);
+        } else {
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+        }
+    }
+
+    // `positions` are ball centers, newest last. Older copies are smaller and fainter
+    pub fn draw_trail(&self, positions: &[Vec2], size: f32) {
+        let Some(trail) = self.trail.as_ref() else {
+            return;
+        };
+        for (i, center) in positions.iter().rev().take(trail.length).enumerate() {
+            let fade = 1f32 - i as f32 / trail.length as f32;
+            let size = size * (0.4f32 + 0.5f32 * fade);
+            let color = Color::new(trail.color.r, trail.color.g, trail.color.b, 0.5f32 * fade);
+            self.draw_ball(Rect::new(center.x - size * 0.5f32, center.y - size * 0.5f32, size, size), color);
+        }
+    }
+}
+
+// A rect turned about its center, drawn as two triangles between its corners. Flat ones skip the
+// rotation so they land on exactly the same pixels as draw_rectangle
+fn draw_turned_rectangle(rect: Rect, tilt: f32, color: Color) {
+    if tilt == 0f32 {
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+        return;
+    }
+    let center = rect.point() + rect.size() * 0.5f32;
+    let along = vec2(tilt.cos(), tilt.sin()) * rect.w * 0.5f32;
+    let down = vec2(-tilt.sin(), tilt.cos()) * rect.h * 0.5f32;
+    let (top_left, top_right) = (center - along - down, center + along - down);
+    let (bottom_right, bottom_left) = (center + along + down, center - along + down);
+    draw_triangle(top_left, top_right, bottom_right, color);
+    draw_triangle(top_left, bottom_right, bottom_left, color);
+}
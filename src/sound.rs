@@ -0,0 +1,199 @@
+use macroquad::audio::{load_sound_from_bytes, play_sound, PlaySoundParams, Sound};
+use macroquad::prelude::*;
+
+use crate::assets::load_asset;
+use crate::Contact;
+
+// Each effect is loaded as several voices at spread-out pitches. A play picks one at random,
+// which both varies the pitch and lets a few copies overlap
+const VOICES_PER_EFFECT: usize = 5;
+// Voices range from this much below to this much above the original pitch
+const PITCH_VARIATION: f32 = 0.1f32;
+// A play this soon after the last one of the same effect is dropped
+const MIN_RETRIGGER_TIME: f64 = 0.03f64;
+// Most effect voices allowed to sound at once across every effect
+const MAX_VOICES: usize = 8;
+// Impacts this fast or faster play at full volume, measured as the change in the ball's velocity
+const FULL_VOLUME_IMPACT: f32 = 2f32;
+const MIN_IMPACT_VOLUME: f32 = 0.3f32;
+
+#[derive(Clone, Copy)]
+pub enum SoundEffect {
+    Bounce,
+    Break,
+}
+
+impl SoundEffect {
+    const ALL: [SoundEffect; 2] = [SoundEffect::Bounce, SoundEffect::Break];
+
+    // No samples ship with the game. Effects play once their files are added to res/sfx
+    fn path(self) -> &'static str {
+        match self {
+            SoundEffect::Bounce => "res/sfx/bounce.wav",
+            SoundEffect::Break => "res/sfx/break.wav",
+        }
+    }
+}
+
+struct Voices {
+    sounds: Vec<Sound>,
+    // Length of the original sample, close enough for every pitch when counting what's still playing
+    duration: f64,
+    last_played: f64,
+}
+
+// Plays short effects with some variety, and keeps dense moments from stacking into one loud blast
+pub struct SoundManager {
+    // Indexed like SoundEffect::ALL, None for effects whose file is missing or isn't 16-bit PCM WAV
+    effects: Vec<Option<Voices>>,
+    // When each voice still sounding will finish
+    playing: Vec<f64>,
+}
+
+impl SoundManager {
+    pub async fn load() -> Self {
+        let mut effects = Vec::new();
+        for effect in SoundEffect::ALL.iter() {
+            effects.push(load_voices(effect.path()).await);
+        }
+        Self {
+            effects,
+            playing: Vec::new(),
+        }
+    }
+
+    pub fn play(&mut self, effect: SoundEffect, volume: f32) {
+        let now = get_time();
+        self.playing.retain(|end| *end > now);
+        let Some(voices) = self.effects[effect as usize].as_mut() else {
+            return;
+        };
+        if self.playing.len() >= MAX_VOICES || now - voices.last_played < MIN_RETRIGGER_TIME {
+            return;
+        }
+
+        let sound = voices.sounds[rand::gen_range(0, voices.sounds.len())];
+        play_sound(
+            sound,
+            PlaySoundParams {
+                looped: false,
+                volume: volume.clamp(0f32, 1f32),
+            },
+        );
+        voices.last_played = now;
+        self.playing.push(now + voices.duration);
+    }
+
+    // Louder the harder the ball was turned around, so glancing hits stay quiet
+    pub fn play_impact(&mut self, effect: SoundEffect, contact: &Contact) {
+        let impact = (contact.vel_after - contact.vel_before).length() / FULL_VOLUME_IMPACT;
+        self.play(effect, impact.max(MIN_IMPACT_VOLUME));
+    }
+}
+
+async fn load_voices(path: &str) -> Option<Voices> {
+    let bytes = load_asset(path).await?;
+    let wav = Wav::parse(&bytes)?;
+
+    let mut sounds = Vec::new();
+    for i in 0..VOICES_PER_EFFECT {
+        let t = i as f32 / (VOICES_PER_EFFECT - 1) as f32;
+        let pitch = 1f32 - PITCH_VARIATION + t * PITCH_VARIATION * 2f32;
+        sounds.push(load_sound_from_bytes(&wav.resampled(pitch)).await.ok()?);
+    }
+    Some(Voices {
+        sounds,
+        duration: wav.duration(),
+        last_played: f64::NEG_INFINITY,
+    })
+}
+
+// Just enough of a 16-bit PCM WAV file to change its pitch and write it back out
+struct Wav {
+    channels: u16,
+    sample_rate: u32,
+    // Interleaved samples
+    samples: Vec<i16>,
+}
+
+impl Wav {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+            return None;
+        }
+        let (mut format, mut samples) = (None, None);
+        let mut offset = 12;
+        while offset + 8 <= bytes.len() {
+            let id = &bytes[offset..offset + 4];
+            let size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().ok()?) as usize;
+            let chunk = bytes.get(offset + 8..offset + 8 + size)?;
+            match id {
+                b"fmt " => {
+                    let audio_format = u16::from_le_bytes(chunk.get(0..2)?.try_into().ok()?);
+                    let channels = u16::from_le_bytes(chunk.get(2..4)?.try_into().ok()?);
+                    let sample_rate = u32::from_le_bytes(chunk.get(4..8)?.try_into().ok()?);
+                    let bits = u16::from_le_bytes(chunk.get(14..16)?.try_into().ok()?);
+                    if audio_format != 1 || bits != 16 || channels == 0 {
+                        return None;
+                    }
+                    format = Some((channels, sample_rate));
+                },
+                b"data" => {
+                    samples = Some(chunk.chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect());
+                },
+                _ => {},
+            }
+            // Chunks are padded to an even length
+            offset += 8 + size + size % 2;
+        }
+        let (channels, sample_rate) = format?;
+        Some(Self {
+            channels,
+            sample_rate,
+            samples: samples?,
+        })
+    }
+
+    fn duration(&self) -> f64 {
+        self.samples.len() as f64 / self.channels as f64 / self.sample_rate as f64
+    }
+
+    // Plays the samples back faster or slower, interpolating between neighbors, and encodes the result
+    fn resampled(&self, pitch: f32) -> Vec<u8> {
+        let channels = self.channels as usize;
+        let frames = self.samples.len() / channels;
+        let new_frames = (frames as f32 / pitch) as usize;
+
+        let mut samples = Vec::with_capacity(new_frames * channels);
+        for frame in 0..new_frames {
+            let position = frame as f32 * pitch;
+            let (index, fraction) = (position as usize, position.fract());
+            let next = (index + 1).min(frames - 1);
+            for channel in 0..channels {
+                let a = self.samples[index * channels + channel] as f32;
+                let b = self.samples[next * channels + channel] as f32;
+                samples.push((a + (b - a) * fraction) as i16);
+            }
+        }
+
+        let data_size = (samples.len() * 2) as u32;
+        let block_align = self.channels * 2;
+        let mut bytes = Vec::with_capacity(44 + data_size as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&self.channels.to_le_bytes());
+        bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(self.sample_rate * block_align as u32).to_le_bytes());
+        bytes.extend_from_slice(&block_align.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        for sample in samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        bytes
+    }
+}
@@ -0,0 +1,93 @@
+pub const STATS_PATH: &str = "stats.json";
+
+// How much of a run is in the totals so far. A run can end in a game over more than once when it's
+// retried or continued, and each time only what came after the last one is added
+#[derive(Clone, Copy, Default)]
+pub struct RunTotals {
+    pub blocks_destroyed: u32,
+    pub play_time: f32,
+    pub score: i32,
+}
+
+// Lifetime totals accumulated across every run, stored as a flat JSON object of numbers
+#[derive(Default)]
+pub struct Stats {
+    pub blocks_destroyed: u64,
+    // Seconds of actual play, not counting menus or pauses
+    pub play_time: f64,
+    pub games_played: u64,
+    pub best_score: i32,
+    // Points from every honest run added up, which unlocks skins
+    pub total_score: u64,
+    // Most blocks one ball destroyed in a single chain
+    pub longest_chain: u32,
+}
+
+impl Stats {
+    // A missing or corrupt file starts the totals from zero rather than failing
+    pub fn load() -> Self {
+        let mut stats = Stats::default();
+        let Ok(text) = std::fs::read_to_string(STATS_PATH) else {
+            return stats;
+        };
+        let Some(body) = text.trim().strip_prefix('{').and_then(|body| body.strip_suffix('}')) else {
+            return stats;
+        };
+
+        for field in body.split(',') {
+            let Some((key, value)) = field.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().trim_matches('"') {
+                "blocks_destroyed" => stats.blocks_destroyed = value.parse().unwrap_or(0),
+                "play_time" => stats.play_time = value.parse().unwrap_or(0f64),
+                "games_played" => stats.games_played = value.parse().unwrap_or(0),
+                "best_score" => stats.best_score = value.parse().unwrap_or(0),
+                "total_score" => stats.total_score = value.parse().unwrap_or(0),
+                "longest_chain" => stats.longest_chain = value.parse().unwrap_or(0),
+                _ => {},
+            }
+        }
+        stats
+    }
+
+    // Writing is best effort, failing just means this session's totals aren't kept
+    pub fn save(&self) {
+        let text = format!(
+            "{{\n  \"blocks_destroyed\": {},\n  \"play_time\": {:.1},\n  \"games_played\": {},\n  \"best_score\": {},\n  \"total_score\": {},\n  \"longest_chain\": {}\n}}\n",
+            self.blocks_destroyed, self.play_time, self.games_played, self.best_score, self.total_score, self.longest_chain,
+        );
+        let _ = std::fs::write(STATS_PATH, text);
+    }
+
+    // Folds a game over into the totals. `run` is where the run stands now and `recorded` what an earlier
+    // game over of the same run already added, if there was one. Only the difference is counted, and
+    // the run only counts as played once. Cheated runs still count as played but can't set a best
+    pub fn record_run(&mut self, run: RunTotals, recorded: Option<RunTotals>, longest_chain: u32, cheated: bool) {
+        let before = recorded.unwrap_or_default();
+        self.blocks_destroyed += run.blocks_destroyed.saturating_sub(before.blocks_destroyed) as u64;
+        self.play_time += (run.play_time - before.play_time).max(0f32) as f64;
+        if recorded.is_none() {
+            self.games_played += 1;
+        }
+        if !cheated {
+            self.best_score = self.best_score.max(run.score);
+            self.total_score += (run.score - before.score.max(0)).max(0) as u64;
+            self.longest_chain = self.longest_chain.max(longest_chain);
+        }
+    }
+
+    // Text ids and values shown on the stats screen, in display order
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let minutes = (self.play_time / 60f64) as u64;
+        vec![
+            ("stats.games_played", self.games_played.to_string()),
+            ("stats.best_score", self.best_score.to_string()),
+            ("stats.total_score", self.total_score.to_string()),
+            ("stats.blocks_destroyed", self.blocks_destroyed.to_string()),
+            ("stats.longest_chain", self.longest_chain.to_string()),
+            ("stats.play_time", format!("{}h {:02}m", minutes / 60, minutes % 60)),
+        ]
+    }
+}
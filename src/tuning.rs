@@ -0,0 +1,113 @@
+use macroquad::prelude::*;
+
+use crate::{DASH_ENGLISH, PADDLE_BOUNCE_CURVE, PADDLE_MAX_BOUNCE_ANGLE};
+
+const PANEL_FONT_SIZE: f32 = 20f32;
+const PANEL_LINE_HEIGHT: f32 = 20f32;
+const PANEL_WIDTH: f32 = 300f32;
+
+// Ball and paddle values that can be changed mid-run from the debug overlay, so tuning doesn't need a
+// rebuild. They start at the constants in main.rs and only ever live in memory
+#[derive(Clone, Copy, PartialEq)]
+pub struct Physics {
+    // Multiplies BALL_SPEED on top of everything else that scales it
+    pub ball_speed: f32,
+    // Extra bounce angle in radians given to a ball hit mid-dash
+    pub dash_english: f32,
+    // Steepest paddle bounce in radians from straight up
+    pub max_bounce_angle: f32,
+    // How the bounce angle bends with where the ball lands on the paddle, 1 being linear
+    pub bounce_curve: f32,
+}
+
+impl Physics {
+    pub const DEFAULT: Physics = Physics {
+        ball_speed: 1f32,
+        dash_english: DASH_ENGLISH,
+        max_bounce_angle: PADDLE_MAX_BOUNCE_ANGLE,
+        bounce_curve: PADDLE_BOUNCE_CURVE,
+    };
+
+    pub fn is_modified(&self) -> bool {
+        *self != Self::DEFAULT
+    }
+
+    fn value(&self, index: usize) -> f32 {
+        let mut copy = *self;
+        *copy.value_mut(index)
+    }
+
+    fn value_mut(&mut self, index: usize) -> &mut f32 {
+        match index {
+            0 => &mut self.ball_speed,
+            1 => &mut self.dash_english,
+            2 => &mut self.max_bounce_angle,
+            _ => &mut self.bounce_curve,
+        }
+    }
+}
+
+// Each tunable value's label, the step one press changes it by, and the range it's held to
+const TUNABLES: [(&str, f32, f32, f32); 4] = [
+    ("ball speed", 0.05f32, 0.25f32, 3f32),
+    ("dash english", 0.05f32, 0f32, 1f32),
+    ("max bounce angle", 0.05f32, 0.2f32, 1.4f32),
+    ("bounce curve", 0.1f32, 0.5f32, 4f32),
+];
+
+// The list of tunable values on the debug overlay. PAGE UP and PAGE DOWN pick one, MINUS and EQUALS
+// change it, and 0 puts everything back to the defaults
+pub struct TuningPanel {
+    selected: usize,
+}
+
+impl TuningPanel {
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+
+    // True if a value changed this frame
+    pub fn update(&mut self, physics: &mut Physics) -> bool {
+        if is_key_pressed(KeyCode::PageDown) {
+            self.selected = (self.selected + 1) % TUNABLES.len();
+        }
+        if is_key_pressed(KeyCode::PageUp) {
+            self.selected = (self.selected + TUNABLES.len() - 1) % TUNABLES.len();
+        }
+        if is_key_pressed(KeyCode::Key0) {
+            let changed = physics.is_modified();
+            *physics = Physics::DEFAULT;
+            return changed;
+        }
+
+        let direction = match (is_key_pressed(KeyCode::Minus), is_key_pressed(KeyCode::Equal)) {
+            (true, false) => -1f32,
+            (false, true) => 1f32,
+            _ => return false,
+        };
+        let (_, step, min, max) = TUNABLES[self.selected];
+        let value = physics.value_mut(self.selected);
+        let before = *value;
+        *value = (*value + step * direction).clamp(min, max);
+        *value != before
+    }
+
+    // Down the right side, with the picked value marked and a note once anything differs from the defaults
+    pub fn draw(&self, physics: &Physics) {
+        let mut lines = Vec::new();
+        for (i, (label, _, _, _)) in TUNABLES.iter().enumerate() {
+            let marker = if i == self.selected { ">" } else { " " };
+            lines.push(format!("{} {}: {:.2}", marker, label, physics.value(i)));
+        }
+        lines.push(if physics.is_modified() { "modified, no high scores".to_string() } else { "defaults".to_string() });
+        lines.push("PGUP/PGDN pick  -/= change  0 reset".to_string());
+
+        let left = screen_width() - PANEL_WIDTH - 10f32;
+        let top = screen_height() * 0.5f32 - lines.len() as f32 * PANEL_LINE_HEIGHT * 0.5f32;
+        draw_rectangle(left - 10f32, top - PANEL_LINE_HEIGHT, PANEL_WIDTH + 20f32, (lines.len() + 1) as f32 * PANEL_LINE_HEIGHT, Color::new(0f32, 0f32, 0f32, 0.6f32));
+        for (i, line) in lines.iter().enumerate() {
+            let color = if i == TUNABLES.len() && physics.is_modified() { ORANGE } else { WHITE };
+            draw_text(line, left, top + i as f32 * PANEL_LINE_HEIGHT, PANEL_FONT_SIZE, color);
+        }
+    }
+}